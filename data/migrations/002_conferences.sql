@@ -0,0 +1,26 @@
+-- Each division belongs to a conference, which decides the wild cards.
+CREATE TABLE conferences (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL
+);
+
+INSERT INTO conferences (name) VALUES ('Eastern'), ('Western');
+
+-- SQLite can't add a NOT NULL column without a default, so divisions is rebuilt. The ids are kept,
+-- since teams refer to them.
+CREATE TABLE new_divisions (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    conference_id INTEGER NOT NULL,
+    FOREIGN KEY (conference_id) REFERENCES conferences (id)
+);
+
+INSERT INTO new_divisions (id, name, conference_id)
+SELECT divisions.id, divisions.name, conferences.id FROM divisions
+JOIN conferences ON conferences.name = CASE
+    WHEN divisions.name IN ('Metropolitan', 'Atlantic') THEN 'Eastern'
+    ELSE 'Western'
+END;
+
+DROP TABLE divisions;
+ALTER TABLE new_divisions RENAME TO divisions;
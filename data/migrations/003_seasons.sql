@@ -0,0 +1,52 @@
+CREATE TABLE seasons (
+    id INTEGER PRIMARY KEY,
+    api_id INTEGER NOT NULL UNIQUE
+);
+
+CREATE TABLE series (
+    id INTEGER PRIMARY KEY,
+    season_id INTEGER NOT NULL,
+    letter TEXT NOT NULL,
+    round INTEGER NOT NULL,
+    needed_to_win INTEGER NOT NULL,
+    UNIQUE (season_id, letter),
+    FOREIGN KEY (season_id) REFERENCES seasons (id)
+);
+
+INSERT INTO seasons (api_id) VALUES (20212022), (20222023), (20232024);
+
+-- A game's api_id starts with the year its season started, like 2023020001 for 20232024.
+INSERT OR IGNORE INTO seasons (api_id)
+SELECT DISTINCT (api_id / 1000000) * 10000 + api_id / 1000000 + 1 FROM games;
+
+-- games is rebuilt to add NOT NULL columns, keeping the ids.
+CREATE TABLE new_games (
+    id INTEGER PRIMARY KEY,
+    api_id INTEGER NOT NULL UNIQUE,
+    home_team_id INTEGER NOT NULL,
+    away_team_id INTEGER NOT NULL,
+    score_id INTEGER,
+    season_id INTEGER NOT NULL,
+    series_id INTEGER,
+    game_number INTEGER,
+    -- Local to the venue, like 2023-10-10.
+    game_date TEXT NOT NULL,
+    -- RFC 3339, like 2023-10-10T23:30:00Z.
+    start_time_utc TEXT NOT NULL,
+    FOREIGN KEY (home_team_id) REFERENCES teams (id),
+    FOREIGN KEY (away_team_id) REFERENCES teams (id),
+    FOREIGN KEY (score_id) REFERENCES scores (id),
+    FOREIGN KEY (season_id) REFERENCES seasons (id),
+    FOREIGN KEY (series_id) REFERENCES series (id)
+);
+
+-- Only regular season games were stored before this, and without their dates. Until the next sync
+-- fills them in, they're dated the start of their season and have no start time.
+INSERT INTO new_games (id, api_id, home_team_id, away_team_id, score_id, season_id, game_date, start_time_utc)
+SELECT games.id, games.api_id, games.home_team_id, games.away_team_id, games.score_id, seasons.id,
+    (games.api_id / 1000000) || '-10-01', ''
+FROM games
+JOIN seasons ON seasons.api_id = (games.api_id / 1000000) * 10000 + games.api_id / 1000000 + 1;
+
+DROP TABLE games;
+ALTER TABLE new_games RENAME TO games;
@@ -0,0 +1,7 @@
+-- Days that have been fetched from the league-wide scoreboard. Syncing skips days that are final.
+CREATE TABLE schedule_days (
+    -- Like 2023-10-10, in the local time at the venues.
+    game_date TEXT PRIMARY KEY,
+    -- 1 once the day has passed and every game on it was finished when it was fetched.
+    final INTEGER NOT NULL
+);
@@ -0,0 +1,6 @@
+-- The api's gameState, like FUT, LIVE, or OFF. Syncing only refreshes games that aren't final.
+ALTER TABLE games ADD COLUMN game_state TEXT NOT NULL DEFAULT 'FUT';
+
+-- Games that already have a score were final when they were fetched. The ones without a start time
+-- (see 003_seasons.sql) are left unfinished, so the next sync fetches their dates.
+UPDATE games SET game_state = 'OFF' WHERE score_id IS NOT NULL AND start_time_utc != '';
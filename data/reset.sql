@@ -1,5 +1,10 @@
+DROP TABLE IF EXISTS schedule_days;
 DROP TABLE IF EXISTS games;
+DROP TABLE IF EXISTS series;
 DROP TABLE IF EXISTS last_period_types;
 DROP TABLE IF EXISTS scores;
 DROP TABLE IF EXISTS divisions;
+DROP TABLE IF EXISTS conferences;
 DROP TABLE IF EXISTS teams;
+DROP TABLE IF EXISTS seasons;
+PRAGMA user_version = 0;
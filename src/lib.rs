@@ -0,0 +1,3324 @@
+//! The data behind hockey plots: NHL schedules and scores, fetched from the NHL api or imported
+//! from its json dumps, stored in SQLite, and turned into standings and per-team points series.
+//!
+//! [`DB`] is the way in. Something like this prints the current standings:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), hockey_plots::Error> {
+//! let db = hockey_plots::DB::initialize("hockeyplots.db".as_ref())?;
+//! let teams = db.get_teams()?;
+//! let season = *db.get_seasons()?.last().expect("no seasons in the db");
+//! for standing in db.standings(&teams, &season)? {
+//!   println!("{} {}", standing.team_api_id, standing.points);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use chrono::{Datelike, NaiveDate};
+use egui::Color32;
+
+use reqwest as request;
+
+/// The schema is built into the binary so a new db can be created from anywhere. Migrations run in
+/// order, and a db's `PRAGMA user_version` is the number of them that have been applied. Only ever
+/// append to this list.
+pub const MIGRATIONS: &[&str] = &[
+  include_str!("../data/migrations/001_init.sql"),
+  include_str!("../data/migrations/002_conferences.sql"),
+  include_str!("../data/migrations/003_seasons.sql"),
+  include_str!("../data/migrations/004_schedule_days.sql"),
+  include_str!("../data/migrations/005_game_state.sql"),
+];
+
+/// Rows as they're stored in SQLite. `db_to_crate` turns each into the types the rest of the crate
+/// uses.
+pub mod db {
+  /// A row of `conferences`.
+  #[derive(Clone, Debug, serde::Deserialize)]
+  pub struct Conference {
+    /// Primary key.
+    pub id: i32,
+    /// Like "Eastern".
+    pub name: String,
+  }
+
+  /// A row of `divisions`.
+  #[derive(Clone, Debug, serde::Deserialize)]
+  pub struct Division {
+    /// Primary key.
+    pub id: i32,
+    /// Like "Metropolitan".
+    pub name: String,
+    /// The conference the division is in.
+    pub conference_id: i32,
+  }
+
+  /// A row of `last_period_types`, for how a game ended.
+  #[derive(Clone, Debug, serde::Deserialize)]
+  pub struct LastPeriodType {
+    /// Primary key.
+    pub id: i32,
+    /// "Regulation", "Overtime", or "Shootout".
+    pub name: String,
+  }
+
+  impl LastPeriodType {
+    /// Fails on any other name.
+    pub fn db_to_crate(&self) -> Result<crate::LastPeriod, crate::Error> {
+      match self.name.as_str() {
+        "Regulation" => Ok(crate::LastPeriod::Regulation),
+        "Overtime" => Ok(crate::LastPeriod::Overtime),
+        "Shootout" => Ok(crate::LastPeriod::Shootout),
+        other => Err(crate::Error::UnknownPeriodType(other.to_string())),
+      }
+    }
+  }
+
+  /// A row of `seasons`.
+  #[derive(Clone, Debug, serde::Deserialize)]
+  pub struct Season {
+    /// Primary key.
+    pub id: i32,
+    /// Both of the season's years, like 20232024.
+    pub api_id: i32,
+  }
+
+  impl Season {
+    /// Into a [`crate::Season`], which has the same ids.
+    pub fn db_to_crate(self) -> crate::Season {
+      crate::Season {
+        db_id: self.id,
+        api_id: self.api_id,
+      }
+    }
+  }
+
+  /// A row of `teams`.
+  #[derive(Clone, Debug, serde::Deserialize)]
+  pub struct Team {
+    /// Primary key.
+    pub id: i32,
+    /// The NHL api's id for the team.
+    pub api_id: i32,
+    /// The red in the team's color, from 0 to 255.
+    pub r: i32,
+    /// The green in the team's color.
+    pub g: i32,
+    /// The blue in the team's color.
+    pub b: i32,
+    /// Like "NYR".
+    pub abbrev: String,
+    /// The division the team plays in.
+    pub division_id: i32,
+  }
+
+  impl Team {
+    /// Looks up the team's division and its conference in the other tables' rows.
+    pub fn db_to_crate(
+      self,
+      db_divisions: &[Division],
+      db_conferences: &[Conference],
+    ) -> Result<crate::Team, crate::Error> {
+      fn find_division(id: i32, db_divisions: &[Division]) -> Result<&Division, crate::Error> {
+        db_divisions
+          .iter()
+          .find(|db_division| db_division.id == id)
+          .ok_or(crate::Error::MissingRow {
+            table: "divisions",
+            id,
+          })
+      }
+
+      fn find_conference(
+        id: i32,
+        db_conferences: &[Conference],
+      ) -> Result<crate::Conference, crate::Error> {
+        let db_conference = db_conferences
+          .iter()
+          .find(|db_conference| db_conference.id == id)
+          .ok_or(crate::Error::MissingRow {
+            table: "conferences",
+            id,
+          })?;
+        match db_conference.name.as_str() {
+          "Eastern" => Ok(crate::Conference::Eastern),
+          "Western" => Ok(crate::Conference::Western),
+          _ => Err(crate::Error::UnknownConference(db_conference.name.clone())),
+        }
+      }
+
+      let db_division = find_division(self.division_id, db_divisions)?;
+      let division = match db_division.name.as_str() {
+        "Metropolitan" => crate::Division::Metropolitan,
+        "Pacific" => crate::Division::Pacific,
+        "Atlantic" => crate::Division::Atlantic,
+        "Central" => crate::Division::Central,
+        _ => return Err(crate::Error::UnknownDivision(db_division.name.clone())),
+      };
+
+      Ok(crate::Team {
+        db_id: self.id,
+        api_id: self.api_id,
+        color: egui::Color32::from_rgb(self.r as u8, self.g as u8, self.b as u8),
+        abbrev: self.abbrev,
+        division,
+        conference: find_conference(db_division.conference_id, db_conferences)?,
+      })
+    }
+  }
+
+  /// A row of `scores`, which finished games point to.
+  #[derive(Copy, Clone, Debug, serde::Deserialize)]
+  pub struct Score {
+    /// Primary key.
+    pub id: i32,
+    /// The home team's goals.
+    pub home: i32,
+    /// The away team's goals.
+    pub away: i32,
+    /// Whether the game ended in regulation, overtime, or a shootout.
+    pub last_period_type_id: i32,
+  }
+
+  /// The columns of `games` that the standings and plots need.
+  #[derive(Clone, Debug, serde::Deserialize)]
+  pub struct Game {
+    /// The home team's id in `teams`.
+    pub home_team_id: i32,
+    /// The away team's id in `teams`.
+    pub away_team_id: i32,
+    /// Only set once the game is finished.
+    pub score_id: Option<i32>,
+    /// Only set for playoff games.
+    pub series_id: Option<i32>,
+    /// Which game of its playoff series this is.
+    pub game_number: Option<i32>,
+    /// The local date at the venue.
+    pub game_date: chrono::NaiveDate,
+  }
+
+  fn find_api_id(db_id: i32, teams: &[crate::Team]) -> Result<i32, crate::Error> {
+    teams
+      .iter()
+      .find(|team| team.db_id == db_id)
+      .map(|team| team.api_id)
+      .ok_or(crate::Error::MissingRow {
+        table: "teams",
+        id: db_id,
+      })
+  }
+
+  impl Game {
+    /// From `this_team`'s point of view. `game_outcome` is the game's score, once it's finished.
+    pub fn db_to_crate(
+      self: Game,
+      game_outcome: Option<(Score, LastPeriodType)>,
+      this_team: &crate::Team,
+      teams: &[crate::Team],
+    ) -> Result<crate::Game, crate::Error> {
+      let home = self.home_team_id == this_team.db_id;
+      let opponent_db_id = if home {
+        self.away_team_id
+      } else {
+        self.home_team_id
+      };
+      let result = if let Some((score, last_period_type)) = game_outcome {
+        let (this_team_score, opponent_score) = if home {
+          (score.home, score.away)
+        } else {
+          (score.away, score.home)
+        };
+        Some(crate::GameResult::new(
+          this_team_score,
+          opponent_score,
+          last_period_type.db_to_crate()?,
+        ))
+      } else {
+        None
+      };
+
+      Ok(crate::Game {
+        date: self.game_date,
+        opponent: find_api_id(opponent_db_id, teams)?,
+        home,
+        result,
+      })
+    }
+  }
+
+  /// A row of `series`, for one playoff matchup.
+  #[derive(Clone, Debug, serde::Deserialize)]
+  pub struct Series {
+    /// Primary key.
+    pub id: i32,
+    /// Which of the season's series it is, like "A".
+    pub letter: String,
+    /// 1 for the first round, up to 4 for the final.
+    pub round: i32,
+    /// How many wins it takes to take the series.
+    pub needed_to_win: i32,
+  }
+
+  impl Series {
+    /// `db_games` are the series' games in order, and `scores` has at least the finished ones' scores.
+    pub fn db_to_crate(
+      self,
+      db_games: &[Game],
+      scores: &[Score],
+      teams: &[crate::Team],
+    ) -> Result<crate::Series, crate::Error> {
+      let games = db_games
+        .iter()
+        .enumerate()
+        .map(|(idx, db_game)| {
+          let home_team = find_api_id(db_game.home_team_id, teams)?;
+          let away_team = find_api_id(db_game.away_team_id, teams)?;
+          let winner = db_game
+            .score_id
+            .and_then(|score_id| scores.iter().find(|score| score.id == score_id))
+            .map(|score| {
+              if score.home > score.away {
+                home_team
+              } else {
+                away_team
+              }
+            });
+          Ok(crate::PlayoffGame {
+            game_number: db_game.game_number.unwrap_or(1 + idx as i32),
+            home_team,
+            away_team,
+            winner,
+          })
+        })
+        .collect::<Result<Vec<crate::PlayoffGame>, crate::Error>>()?;
+
+      Ok(crate::Series {
+        letter: self.letter.chars().next().unwrap_or('?'),
+        round: self.round,
+        needed_to_win: self.needed_to_win,
+        games,
+      })
+    }
+  }
+}
+
+/// The NHL api's `club-schedule-season` json, and the ways to get it. `api_to_crate` turns games
+/// into the types the rest of the crate uses.
+pub mod json {
+  use serde::Deserialize;
+
+  /// One side of a game.
+  #[derive(Debug, Deserialize, Copy, Clone)]
+  pub struct GameTeam {
+    /// The team's api id.
+    pub id: i32,
+    /// Goals, once the game has started.
+    pub score: Option<i32>,
+  }
+
+  /// How a game ended.
+  #[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq, Hash)]
+  #[allow(clippy::upper_case_acronyms)]
+  pub enum PeriodType {
+    /// Shootout.
+    SO,
+    /// Regulation.
+    REG,
+    /// Overtime.
+    OT,
+  }
+
+  impl PeriodType {
+    /// The same thing, as a [`crate::LastPeriod`].
+    pub fn api_to_crate(self) -> crate::LastPeriod {
+      match self {
+        PeriodType::REG => crate::LastPeriod::Regulation,
+        PeriodType::OT => crate::LastPeriod::Overtime,
+        PeriodType::SO => crate::LastPeriod::Shootout,
+      }
+    }
+  }
+
+  /// Where a game is at. These are stored in `games.game_state` as they're named.
+  #[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+  #[allow(clippy::upper_case_acronyms)]
+  pub enum GameState {
+    /// Scheduled.
+    FUT,
+    /// Warmups.
+    PRE,
+    /// Being played.
+    LIVE,
+    /// The last few minutes of a close game.
+    CRIT,
+    /// The game just ended.
+    OVER,
+    /// Over, but not official yet.
+    FINAL,
+    /// Official.
+    OFF,
+    /// Anything the api adds later. It's treated like a game that isn't over, so it gets fetched
+    /// again.
+    #[serde(other)]
+    Unknown,
+  }
+
+  impl GameState {
+    /// Every state, in the order a game goes through them, and then `Unknown`.
+    pub const ALL: [GameState; 8] = [
+      GameState::FUT,
+      GameState::PRE,
+      GameState::LIVE,
+      GameState::CRIT,
+      GameState::OVER,
+      GameState::FINAL,
+      GameState::OFF,
+      GameState::Unknown,
+    ];
+
+    /// The states that won't change anymore, in a form that can go in an SQL `IN (...)`.
+    pub const FINAL_SQL: &'static str = "'OVER', 'FINAL', 'OFF'";
+
+    /// Whether the game is over and its result won't change.
+    pub fn is_final(self) -> bool {
+      matches!(self, GameState::OVER | GameState::FINAL | GameState::OFF)
+    }
+
+    /// The opposite of `as_str`.
+    pub fn parse(text: &str) -> Option<Self> {
+      Self::ALL.into_iter().find(|state| state.as_str() == text)
+    }
+
+    /// What the api calls the state, which is also how it's stored.
+    pub fn as_str(self) -> &'static str {
+      match self {
+        GameState::FUT => "FUT",
+        GameState::PRE => "PRE",
+        GameState::LIVE => "LIVE",
+        GameState::CRIT => "CRIT",
+        GameState::OVER => "OVER",
+        GameState::FINAL => "FINAL",
+        GameState::OFF => "OFF",
+        GameState::Unknown => "UNKNOWN",
+      }
+    }
+  }
+
+  /// How a finished game ended.
+  #[derive(Debug, Deserialize, Copy, Clone)]
+  #[serde(rename_all = "camelCase")]
+  pub struct GameOutcome {
+    /// Whether it was over after regulation, overtime, or a shootout.
+    pub last_period_type: PeriodType,
+  }
+
+  /// Where a playoff game fits in its series.
+  #[derive(Debug, Deserialize, Copy, Clone)]
+  #[serde(rename_all = "camelCase")]
+  pub struct SeriesStatus {
+    /// 1 for the first round, up to 4 for the final.
+    pub round: i32,
+    /// Which of the season's series it is, like 'A'.
+    pub series_letter: char,
+    /// How many wins it takes to take the series.
+    pub needed_to_win: i32,
+    /// Starts at 1.
+    pub game_number_of_series: i32,
+  }
+
+  /// One game, as it shows up in a schedule or on a scoreboard.
+  #[derive(Debug, Deserialize, Copy, Clone)]
+  #[serde(rename_all = "camelCase")]
+  pub struct Game {
+    /// The game's api id, like 2023020005. It starts with the season's first year.
+    pub id: i32,
+    /// The season's api id, like 20232024.
+    pub season: i32,
+    /// The home team, and its score once the game has started.
+    pub home_team: GameTeam,
+    /// The away team, and its score once the game has started.
+    pub away_team: GameTeam,
+    /// 1 for preseason, 2 for regular season, and 3 for playoffs. See `get_type`.
+    pub game_type: u8,
+    /// The local date at the venue, which is what the schedule is published in.
+    pub game_date: chrono::NaiveDate,
+    /// When the game starts.
+    #[serde(rename = "startTimeUTC")]
+    pub start_time_utc: chrono::DateTime<chrono::Utc>,
+    /// Where the game is at.
+    pub game_state: GameState,
+    /// Only present once the game is finished.
+    pub game_outcome: Option<GameOutcome>,
+    /// Only present for playoff games.
+    pub series_status: Option<SeriesStatus>,
+  }
+
+  /// The kinds of games in a schedule.
+  pub enum GameType {
+    /// Exhibition games, which are left out of the db.
+    Preseason,
+    /// Counts in the standings.
+    RegularSeason,
+    /// Belongs to a playoff series.
+    Playoffs,
+  }
+
+  impl Game {
+    /// Fails for other kinds of games, like the All-Star game.
+    pub fn get_type(self) -> Result<GameType, crate::Error> {
+      use crate::json::GameType::*;
+
+      match self.game_type {
+        1 => Ok(Preseason),
+        2 => Ok(RegularSeason),
+        3 => Ok(Playoffs),
+        _ => Err(crate::Error::UnknownGameType(self.game_type)),
+      }
+    }
+
+    /// From `this_team`'s point of view.
+    pub fn api_to_crate(self, this_team: &crate::Team) -> crate::Game {
+      let home = self.home_team.id == this_team.api_id;
+      let (this_team, opponent) = if home {
+        (self.home_team, self.away_team)
+      } else {
+        (self.away_team, self.home_team)
+      };
+      let (this_team_score, opponent_score) = (this_team.score, opponent.score);
+
+      let result = match (self.game_outcome, this_team_score, opponent_score) {
+        (Some(outcome), Some(this_team_score), Some(opponent_score)) => {
+          Some(crate::GameResult::new(
+            this_team_score,
+            opponent_score,
+            outcome.last_period_type.api_to_crate(),
+          ))
+        }
+        _ => None,
+      };
+
+      crate::Game {
+        date: self.game_date,
+        opponent: opponent.id,
+        home,
+        result,
+      }
+    }
+
+    /// The game as part of its playoff series, with `series_status` from the same game.
+    pub fn api_to_playoff_game(self, series_status: SeriesStatus) -> crate::PlayoffGame {
+      let winner = match (
+        self.game_outcome,
+        self.home_team.score,
+        self.away_team.score,
+      ) {
+        (Some(_), Some(home_score), Some(away_score)) => Some(if home_score > away_score {
+          self.home_team.id
+        } else {
+          self.away_team.id
+        }),
+        _ => None,
+      };
+
+      crate::PlayoffGame {
+        game_number: series_status.game_number_of_series,
+        home_team: self.home_team.id,
+        away_team: self.away_team.id,
+        winner,
+      }
+    }
+  }
+
+  /// One team's `club-schedule-season` response.
+  #[derive(Clone, Debug, Deserialize)]
+  pub struct TeamSchedule {
+    /// Every game the team plays in the season, preseason and playoffs included, in order.
+    pub games: Vec<Game>,
+  }
+
+  impl TeamSchedule {
+    /// The schedule doesn't say whose it is, but that team is the only one that plays in every game.
+    pub fn team_api_id(&self) -> Option<i32> {
+      let first_game = self.games.first()?;
+      [first_game.home_team.id, first_game.away_team.id]
+        .into_iter()
+        .find(|team_api_id| {
+          self
+            .games
+            .iter()
+            .all(|game| game.home_team.id == *team_api_id || game.away_team.id == *team_api_id)
+        })
+    }
+
+    /// The season's api id, like 20232024, if the schedule has any games.
+    pub fn season(&self) -> Option<i32> {
+      self.games.first().map(|game| game.season)
+    }
+
+    /// How many of the games have a result.
+    pub fn finished_games(&self) -> usize {
+      self
+        .games
+        .iter()
+        .filter(|game| game.game_outcome.is_some())
+        .count()
+    }
+  }
+
+  /// The league-wide `score/{date}` response, which has every game on one day, once.
+  #[derive(Clone, Debug, Deserialize)]
+  pub struct Scoreboard {
+    /// Every game that day, from every season type.
+    pub games: Vec<Game>,
+  }
+
+  /// Every team's schedule for one season, which is what gets inserted into the db.
+  #[derive(Clone, Debug, Deserialize)]
+  pub struct ApiResponse {
+    /// This is the season's api_id, like 20232024.
+    pub season: i32,
+    /// Key is `team.api_id`.
+    pub schedules: std::collections::HashMap<i32, TeamSchedule>,
+  }
+
+  impl ApiResponse {
+    /// Builds each team's schedule from a list of games that each show up once, like the ones on
+    /// scoreboards. Games from other seasons are left out.
+    pub fn from_games(season: i32, games: impl IntoIterator<Item = Game>) -> Self {
+      let mut schedules: std::collections::HashMap<i32, TeamSchedule> =
+        std::collections::HashMap::new();
+      for game in games.into_iter().filter(|game| game.season == season) {
+        for team_api_id in [game.home_team.id, game.away_team.id] {
+          schedules
+            .entry(team_api_id)
+            .or_insert_with(|| TeamSchedule { games: vec![] })
+            .games
+            .push(game);
+        }
+      }
+      Self { season, schedules }
+    }
+
+    /// Each finished regular season game, once.
+    pub fn finished_games(&self) -> Result<Vec<crate::standings::FinishedGame>, crate::Error> {
+      let mut finished_games = std::collections::BTreeMap::new();
+      for schedule in self.schedules.values() {
+        for game in &schedule.games {
+          if let (GameType::RegularSeason, Some(outcome), Some(home_score), Some(away_score)) = (
+            game.get_type()?,
+            game.game_outcome,
+            game.home_team.score,
+            game.away_team.score,
+          ) {
+            finished_games.insert(
+              game.id,
+              crate::standings::FinishedGame {
+                api_id: game.id,
+                home_team: game.home_team.id,
+                away_team: game.away_team.id,
+                home_score,
+                away_score,
+                last_period: outcome.last_period_type.api_to_crate(),
+              },
+            );
+          }
+        }
+      }
+      Ok(finished_games.into_values().collect())
+    }
+
+    /// Each playoff series, with its games in order.
+    pub fn playoff_series(&self) -> Result<Vec<crate::Series>, crate::Error> {
+      // Each playoff game shows up in both participants' schedules.
+      let mut seen_game_ids = std::collections::HashSet::new();
+      let mut series_by_letter: std::collections::BTreeMap<char, crate::Series> =
+        std::collections::BTreeMap::new();
+
+      for schedule in self.schedules.values() {
+        for game in &schedule.games {
+          if let (GameType::Playoffs, Some(status)) = (game.get_type()?, game.series_status) {
+            if !seen_game_ids.insert(game.id) {
+              continue;
+            }
+            series_by_letter
+              .entry(status.series_letter)
+              .or_insert_with(|| crate::Series {
+                letter: status.series_letter,
+                round: status.round,
+                needed_to_win: status.needed_to_win,
+                games: vec![],
+              })
+              .games
+              .push(game.api_to_playoff_game(status));
+          }
+        }
+      }
+
+      let mut all_series: Vec<crate::Series> = series_by_letter.into_values().collect();
+      for series in all_series.iter_mut() {
+        series.games.sort_by_key(|game| game.game_number);
+      }
+      Ok(all_series)
+    }
+  }
+
+  /// Reads schedules from json dumps of the api, like the ones in `data/`.
+  pub mod files {
+    use crate::*;
+
+    /// What `load_dir` read.
+    #[derive(Clone, Debug)]
+    pub struct LoadedDir {
+      /// One per season, oldest first.
+      pub api_responses: Vec<json::ApiResponse>,
+      /// Files that aren't team schedules, and why.
+      pub skipped: Vec<(std::path::PathBuf, String)>,
+    }
+
+    /// Reads a directory of `club-schedule-season` dumps (like the ones in data/) into one
+    /// ApiResponse per season. Files that aren't team schedules are skipped, and left for the
+    /// caller to report.
+    pub fn load_dir(dir: &Path) -> Result<LoadedDir, Error> {
+      let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+      paths.sort();
+
+      let mut api_responses: HashMap<i32, json::ApiResponse> = HashMap::new();
+      let mut skipped = vec![];
+      for path in paths {
+        let json_contents = std::fs::read_to_string(&path)?;
+        let schedule: json::TeamSchedule = match serde_json::from_str(&json_contents) {
+          Ok(schedule) => schedule,
+          Err(e) => {
+            skipped.push((path, e.to_string()));
+            continue;
+          }
+        };
+        let (Some(team_api_id), Some(season)) = (schedule.team_api_id(), schedule.season()) else {
+          skipped.push((path, "could not find the schedule's team".to_string()));
+          continue;
+        };
+
+        let api_response = api_responses
+          .entry(season)
+          .or_insert_with(|| json::ApiResponse {
+            season,
+            schedules: HashMap::new(),
+          });
+        // When there are several dumps for the same team (like EDM.json and unf-EDM.json),
+        // keep the most up-to-date one.
+        match api_response.schedules.get(&team_api_id) {
+          Some(existing) if existing.finished_games() >= schedule.finished_games() => (),
+          _ => {
+            api_response.schedules.insert(team_api_id, schedule);
+          }
+        }
+      }
+
+      let mut api_responses: Vec<json::ApiResponse> = api_responses.into_values().collect();
+      api_responses.sort_by_key(|api_response| api_response.season);
+      Ok(LoadedDir {
+        api_responses,
+        skipped,
+      })
+    }
+  }
+}
+
+/// Where schedules come from: the NHL api, a directory of its json dumps, or schedules that are
+/// already in memory. Each one is a [`source::ScheduleSource`], so callers can pick at runtime.
+pub mod source {
+  use crate::*;
+  use std::future::Future;
+  use std::pin::Pin;
+  use std::sync::Arc;
+  use std::time::Duration;
+
+  /// What [`ScheduleSource::load_season`] returns. It's boxed so sources can be trait objects.
+  pub type ScheduleFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<json::ApiResponse, Error>> + Send + 'a>>;
+
+  /// Anything that can produce a season's schedules, which then go to `DB::insert_schedules`.
+  pub trait ScheduleSource: Send + Sync + std::fmt::Debug {
+    /// Each of `teams`' schedules for the season with `season_api_id`, like 20232024. Teams the
+    /// source doesn't have are left out.
+    fn load_season<'a>(&'a self, teams: &'a [Team], season_api_id: i32) -> ScheduleFuture<'a>;
+
+    /// Where the schedules come from, for status messages.
+    fn describe(&self) -> String;
+  }
+
+  /// The longest wait between retries, however many there are.
+  pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+  /// Fetches each team's schedule from api-web.nhle.com, several at a time but no faster than
+  /// `requests_per_second`.
+  #[derive(Clone, Debug)]
+  pub struct NhlApi {
+    /// The most requests in flight at once.
+    pub concurrency: usize,
+    /// Including retries.
+    pub requests_per_second: f64,
+    /// For each request, including reading the response.
+    pub timeout: Duration,
+    /// How many times a request is retried after a timeout, a connection error, or a 429 or 5xx
+    /// response. Other errors fail right away.
+    pub retries: u32,
+    /// How long to wait before the first retry. This doubles for each one after that, up to
+    /// [`MAX_BACKOFF`].
+    pub backoff: Duration,
+  }
+
+  impl Default for NhlApi {
+    fn default() -> Self {
+      Self {
+        concurrency: 4,
+        requests_per_second: 8.0,
+        timeout: Duration::from_secs(10),
+        retries: 3,
+        backoff: Duration::from_millis(500),
+      }
+    }
+  }
+
+  impl NhlApi {
+    /// The league-wide scoreboard for each of `dates`, so each game is only fetched once.
+    pub async fn load_days(
+      &self,
+      dates: &[NaiveDate],
+    ) -> Result<Vec<(NaiveDate, json::Scoreboard)>, Error> {
+      let urls = dates
+        .iter()
+        .map(|date| (*date, format!("https://api-web.nhle.com/v1/score/{date}")))
+        .collect();
+      let mut scoreboards = vec![];
+      for (date, json_contents) in self.get_all(urls).await? {
+        scoreboards.push((date, serde_json::from_str(&json_contents)?));
+      }
+      scoreboards.sort_by_key(|(date, _)| *date);
+      Ok(scoreboards)
+    }
+
+    /// Fetches each url, and returns its body along with its key, in no particular order.
+    async fn get_all<K: Send + 'static>(
+      &self,
+      urls: Vec<(K, String)>,
+    ) -> Result<Vec<(K, String)>, Error> {
+      let client = request::Client::builder().timeout(self.timeout).build()?;
+      let semaphore = Arc::new(tokio::sync::Semaphore::new(self.concurrency.max(1)));
+      let rate_limiter = Arc::new(RateLimiter::new(self.requests_per_second));
+
+      // Dropping this on an error cancels the rest of the requests.
+      let mut requests = tokio::task::JoinSet::new();
+      for (key, url) in urls {
+        let (client, semaphore, rate_limiter) =
+          (client.clone(), semaphore.clone(), rate_limiter.clone());
+        let (retries, backoff) = (self.retries, self.backoff);
+        requests.spawn(async move {
+          // The semaphore is never closed, so this can't fail.
+          let _permit = semaphore.acquire().await;
+          let body = get(&client, &url, &rate_limiter, retries, backoff).await;
+          (key, body)
+        });
+      }
+
+      let mut bodies = vec![];
+      while let Some(result) = requests.join_next().await {
+        let (key, body) = result.map_err(std::io::Error::from)?;
+        bodies.push((key, body?));
+      }
+      Ok(bodies)
+    }
+  }
+
+  impl ScheduleSource for NhlApi {
+    fn load_season<'a>(&'a self, teams: &'a [Team], season_api_id: i32) -> ScheduleFuture<'a> {
+      Box::pin(async move {
+        let urls = teams
+          .iter()
+          .map(|team| {
+            let url = format!(
+              "https://api-web.nhle.com/v1/club-schedule-season/{}/{}",
+              team.abbrev, season_api_id
+            );
+            (team.api_id, url)
+          })
+          .collect();
+        let mut api_response = json::ApiResponse {
+          season: season_api_id,
+          schedules: HashMap::new(),
+        };
+        for (team_api_id, json_contents) in self.get_all(urls).await? {
+          let schedule: json::TeamSchedule = serde_json::from_str(&json_contents)?;
+          api_response.schedules.insert(team_api_id, schedule);
+        }
+        Ok(api_response)
+      })
+    }
+
+    fn describe(&self) -> String {
+      "the NHL api".to_string()
+    }
+  }
+
+  async fn get(
+    client: &request::Client,
+    url: &str,
+    rate_limiter: &RateLimiter,
+    retries: u32,
+    backoff: Duration,
+  ) -> Result<String, request::Error> {
+    fn is_transient(e: &request::Error) -> bool {
+      e.is_timeout()
+        || e.is_connect()
+        || e.status().is_some_and(|status| {
+          status == request::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+        })
+    }
+
+    let mut attempt = 0;
+    loop {
+      rate_limiter.wait().await;
+      let response = async {
+        client
+          .get(url)
+          .send()
+          .await?
+          .error_for_status()?
+          .text()
+          .await
+      };
+      match response.await {
+        Err(e) if attempt < retries && is_transient(&e) => {
+          let delay = 2u32
+            .checked_pow(attempt)
+            .and_then(|factor| backoff.checked_mul(factor))
+            .map_or(MAX_BACKOFF, |delay| delay.min(MAX_BACKOFF));
+          tokio::time::sleep(delay).await;
+          attempt += 1;
+        }
+        response => return response,
+      }
+    }
+  }
+
+  /// Spaces requests out evenly, so bursts don't go over the rate either.
+  #[derive(Debug)]
+  struct RateLimiter {
+    interval: Duration,
+    next: std::sync::Mutex<tokio::time::Instant>,
+  }
+
+  impl RateLimiter {
+    fn new(requests_per_second: f64) -> Self {
+      Self {
+        interval: Duration::from_secs_f64(1.0 / requests_per_second.max(0.001)),
+        next: std::sync::Mutex::new(tokio::time::Instant::now()),
+      }
+    }
+
+    async fn wait(&self) {
+      let start = {
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let start = (*next).max(tokio::time::Instant::now());
+        *next = start + self.interval;
+        start
+      };
+      tokio::time::sleep_until(start).await;
+    }
+  }
+
+  /// Reads `club-schedule-season` dumps from a directory, like the ones in `data/`.
+  #[derive(Clone, Debug)]
+  pub struct Files {
+    /// The directory with the dumps. Every `.json` file in it is read, but not subdirectories.
+    pub dir: std::path::PathBuf,
+  }
+
+  impl ScheduleSource for Files {
+    fn load_season<'a>(&'a self, teams: &'a [Team], season_api_id: i32) -> ScheduleFuture<'a> {
+      Box::pin(async move {
+        let loaded = json::files::load_dir(&self.dir)?;
+        for (path, reason) in &loaded.skipped {
+          log::warn!("skipping {}: {reason}", path.display());
+        }
+        let api_response = loaded
+          .api_responses
+          .into_iter()
+          .find(|api_response| api_response.season == season_api_id)
+          .ok_or(Error::MissingSeason(season_api_id))?;
+        Ok(only_teams(api_response, teams))
+      })
+    }
+
+    fn describe(&self) -> String {
+      self.dir.display().to_string()
+    }
+  }
+
+  /// Schedules that are already in memory, for tests.
+  #[derive(Clone, Debug, Default)]
+  pub struct Fixture {
+    /// Key is the season's api_id.
+    pub api_responses: HashMap<i32, json::ApiResponse>,
+  }
+
+  impl Fixture {
+    /// One schedule per season. A later one for the same season replaces an earlier one.
+    pub fn new(api_responses: impl IntoIterator<Item = json::ApiResponse>) -> Self {
+      Self {
+        api_responses: api_responses
+          .into_iter()
+          .map(|api_response| (api_response.season, api_response))
+          .collect(),
+      }
+    }
+  }
+
+  impl ScheduleSource for Fixture {
+    fn load_season<'a>(&'a self, teams: &'a [Team], season_api_id: i32) -> ScheduleFuture<'a> {
+      Box::pin(async move {
+        let api_response = self
+          .api_responses
+          .get(&season_api_id)
+          .cloned()
+          .ok_or(Error::MissingSeason(season_api_id))?;
+        Ok(only_teams(api_response, teams))
+      })
+    }
+
+    fn describe(&self) -> String {
+      "fixture".to_string()
+    }
+  }
+
+  fn only_teams(mut api_response: json::ApiResponse, teams: &[Team]) -> json::ApiResponse {
+    api_response
+      .schedules
+      .retain(|team_api_id, _| teams.iter().any(|team| team.api_id == *team_api_id));
+    api_response
+  }
+}
+
+/// Standings and playoff seeds, computed from finished games.
+pub mod standings {
+  use crate::*;
+
+  /// A finished regular season game. Teams are identified by their api_id.
+  #[derive(Copy, Clone, Debug)]
+  pub struct FinishedGame {
+    /// The game's api id, like 2023020005.
+    pub api_id: i32,
+    /// The home team's api id.
+    pub home_team: i32,
+    /// The away team's api id.
+    pub away_team: i32,
+    /// The home team's goals.
+    pub home_score: i32,
+    /// The away team's goals.
+    pub away_score: i32,
+    /// Whether it was over after regulation, overtime, or a shootout.
+    pub last_period: LastPeriod,
+  }
+
+  impl FinishedGame {
+    /// From `team_api_id`'s point of view, or `None` if it didn't play in the game.
+    pub fn result_for(&self, team_api_id: i32) -> Option<GameResult> {
+      if team_api_id == self.home_team {
+        Some(GameResult::new(
+          self.home_score,
+          self.away_score,
+          self.last_period,
+        ))
+      } else if team_api_id == self.away_team {
+        Some(GameResult::new(
+          self.away_score,
+          self.home_score,
+          self.last_period,
+        ))
+      } else {
+        None
+      }
+    }
+
+    /// Whether `team_a` and `team_b` played each other, in either building.
+    pub fn is_between(&self, team_a: i32, team_b: i32) -> bool {
+      (self.home_team == team_a && self.away_team == team_b)
+        || (self.home_team == team_b && self.away_team == team_a)
+    }
+  }
+
+  /// One team's line in the standings.
+  #[derive(Clone, Debug, Default, serde::Serialize)]
+  pub struct Standing {
+    /// The team's api id.
+    pub team_api_id: i32,
+    /// Finished games.
+    pub games_played: i32,
+    /// Wins in regulation, overtime, or a shootout.
+    pub wins: i32,
+    /// Regulation losses.
+    pub losses: i32,
+    /// Includes shootout losses, like the NHL's standings do.
+    pub ot_losses: i32,
+    /// 2 per win and 1 per overtime or shootout loss.
+    pub points: i32,
+    /// Wins in regulation.
+    pub regulation_wins: i32,
+    /// Regulation plus overtime wins, so everything but shootout wins.
+    pub regulation_and_ot_wins: i32,
+    /// Goals scored.
+    pub goals_for: i32,
+    /// Goals allowed.
+    pub goals_against: i32,
+  }
+
+  impl Standing {
+    /// Counts one more finished game.
+    pub fn add(&mut self, result: GameResult) {
+      self.games_played += 1;
+      self.goals_for += result.goals_for;
+      self.goals_against += result.goals_against;
+      self.points += PointsSystem::Nhl.points(&result) as i32;
+      match result.decision() {
+        Decision::W => {
+          self.wins += 1;
+          match result.last_period {
+            LastPeriod::Regulation => {
+              self.regulation_wins += 1;
+              self.regulation_and_ot_wins += 1;
+            }
+            LastPeriod::Overtime => self.regulation_and_ot_wins += 1,
+            LastPeriod::Shootout => (),
+          }
+        }
+        Decision::L => self.losses += 1,
+        Decision::OTL | Decision::SOL => self.ot_losses += 1,
+      }
+    }
+
+    /// Points out of the most the team could have had, from 0 to 1.
+    pub fn points_percentage(&self) -> f32 {
+      if self.games_played == 0 {
+        0.0
+      } else {
+        self.points as f32 / (2 * self.games_played) as f32
+      }
+    }
+
+    /// Goals for minus goals against.
+    pub fn goal_differential(&self) -> i32 {
+      self.goals_for - self.goals_against
+    }
+  }
+
+  /// A column of the standings table.
+  #[derive(Copy, Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+  pub enum Column {
+    /// Place in the standings.
+    Rank,
+    /// The team's abbrev.
+    Team,
+    /// Games played.
+    GamesPlayed,
+    /// Wins.
+    Wins,
+    /// Regulation losses.
+    Losses,
+    /// Overtime and shootout losses.
+    OtLosses,
+    /// Points.
+    Points,
+    /// Points percentage.
+    PointsPercentage,
+    /// Regulation wins.
+    RegulationWins,
+    /// Regulation and overtime wins.
+    RegulationAndOtWins,
+    /// Goals for.
+    GoalsFor,
+    /// Goals against.
+    GoalsAgainst,
+    /// Goal differential.
+    GoalDifferential,
+  }
+
+  impl Column {
+    /// Every column, in the order they're shown.
+    pub const ALL: [Column; 13] = [
+      Column::Rank,
+      Column::Team,
+      Column::GamesPlayed,
+      Column::Wins,
+      Column::Losses,
+      Column::OtLosses,
+      Column::Points,
+      Column::PointsPercentage,
+      Column::RegulationWins,
+      Column::RegulationAndOtWins,
+      Column::GoalsFor,
+      Column::GoalsAgainst,
+      Column::GoalDifferential,
+    ];
+
+    /// The column's header, like "PTS".
+    pub fn label(self) -> &'static str {
+      match self {
+        Column::Rank => "#",
+        Column::Team => "team",
+        Column::GamesPlayed => "GP",
+        Column::Wins => "W",
+        Column::Losses => "L",
+        Column::OtLosses => "OTL",
+        Column::Points => "PTS",
+        Column::PointsPercentage => "P%",
+        Column::RegulationWins => "RW",
+        Column::RegulationAndOtWins => "ROW",
+        Column::GoalsFor => "GF",
+        Column::GoalsAgainst => "GA",
+        Column::GoalDifferential => "DIFF",
+      }
+    }
+
+    /// Rank and Team don't have a numeric value, so they're sorted by the caller.
+    pub fn value(self, standing: &Standing) -> f32 {
+      match self {
+        Column::Rank | Column::Team => 0.0,
+        Column::GamesPlayed => standing.games_played as f32,
+        Column::Wins => standing.wins as f32,
+        Column::Losses => standing.losses as f32,
+        Column::OtLosses => standing.ot_losses as f32,
+        Column::Points => standing.points as f32,
+        Column::PointsPercentage => standing.points_percentage(),
+        Column::RegulationWins => standing.regulation_wins as f32,
+        Column::RegulationAndOtWins => standing.regulation_and_ot_wins as f32,
+        Column::GoalsFor => standing.goals_for as f32,
+        Column::GoalsAgainst => standing.goals_against as f32,
+        Column::GoalDifferential => standing.goal_differential() as f32,
+      }
+    }
+
+    /// The column's value, formatted for the table.
+    pub fn text(self, standing: &Standing) -> String {
+      match self {
+        Column::PointsPercentage => format!("{:.3}", standing.points_percentage()),
+        Column::GoalDifferential => format!("{:+}", standing.goal_differential()),
+        _ => format!("{}", self.value(standing)),
+      }
+    }
+  }
+
+  /// Each team in `group`'s points percentage in games against the rest of the group, since teams
+  /// in a tie of three or more might not have played each other as often. When two teams played an
+  /// odd number of games against each other, the first game in the city that hosted the extra game
+  /// doesn't count.
+  fn head_to_head_percentage(group: &[i32], games: &[FinishedGame]) -> HashMap<i32, f32> {
+    let mut group_games: Vec<&FinishedGame> = games
+      .iter()
+      .filter(|game| group.contains(&game.home_team) && group.contains(&game.away_team))
+      .collect();
+    if let ([team_a, team_b], true) = (group, group_games.len() % 2 == 1) {
+      let a_home_games = group_games
+        .iter()
+        .filter(|game| game.home_team == *team_a)
+        .count();
+      let extra_host = if 2 * a_home_games > group_games.len() {
+        *team_a
+      } else {
+        *team_b
+      };
+      if let Some(first) = group_games
+        .iter()
+        .position(|game| game.home_team == extra_host)
+      {
+        group_games.remove(first);
+      }
+    }
+
+    group
+      .iter()
+      .map(|team| {
+        let mut standing = Standing::default();
+        for game in &group_games {
+          if let Some(result) = game.result_for(*team) {
+            standing.add(result);
+          }
+        }
+        (*team, standing.points_percentage())
+      })
+      .collect()
+  }
+
+  /// Returns one Standing for each of `team_api_ids`, sorted using the NHL's tiebreakers: points,
+  /// then fewer games played, regulation wins, regulation and overtime wins, wins, points percentage
+  /// in games between the tied teams, goal differential, and goals for.
+  pub fn compute(team_api_ids: &[i32], games: &[FinishedGame]) -> Vec<Standing> {
+    let mut games = games.to_vec();
+    games.sort_by_key(|game| game.api_id);
+
+    let mut standings: Vec<Standing> = team_api_ids
+      .iter()
+      .map(|team_api_id| Standing {
+        team_api_id: *team_api_id,
+        ..Default::default()
+      })
+      .collect();
+    for standing in standings.iter_mut() {
+      for game in &games {
+        if let Some(result) = game.result_for(standing.team_api_id) {
+          standing.add(result);
+        }
+      }
+    }
+
+    fn record_key(standing: &Standing) -> (i32, i32, i32, i32, i32) {
+      (
+        standing.points,
+        -standing.games_played,
+        standing.regulation_wins,
+        standing.regulation_and_ot_wins,
+        standing.wins,
+      )
+    }
+    standings.sort_by_key(|standing| std::cmp::Reverse(record_key(standing)));
+
+    // Break the remaining ties within each group of teams with identical records.
+    let mut start = 0;
+    while start < standings.len() {
+      let key = record_key(&standings[start]);
+      let end = start
+        + standings[start..]
+          .iter()
+          .take_while(|standing| record_key(standing) == key)
+          .count();
+      if end - start > 1 {
+        let group: Vec<i32> = standings[start..end]
+          .iter()
+          .map(|standing| standing.team_api_id)
+          .collect();
+        let h2h = head_to_head_percentage(&group, &games);
+        standings[start..end].sort_by(|a, b| {
+          h2h[&b.team_api_id]
+            .total_cmp(&h2h[&a.team_api_id])
+            .then(b.goal_differential().cmp(&a.goal_differential()))
+            .then(b.goals_for.cmp(&a.goals_for))
+        });
+      }
+      start = end;
+    }
+
+    standings
+  }
+
+  /// Where a team would land in the playoffs if the season ended today.
+  #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+  pub enum Seed {
+    /// 1-3 within the team's division.
+    Division(usize),
+    /// 1-2 within the team's conference.
+    WildCard(usize),
+    /// Not in a playoff spot.
+    Out,
+  }
+
+  impl Seed {
+    /// How many teams from each division make it.
+    pub const DIVISION_SPOTS: usize = 3;
+    /// How many more teams from each conference make it.
+    pub const WILD_CARD_SPOTS: usize = 2;
+
+    /// Like "D1", "WC2", or "out".
+    pub fn label(self) -> String {
+      match self {
+        Seed::Division(rank) => format!("D{rank}"),
+        Seed::WildCard(rank) => format!("WC{rank}"),
+        Seed::Out => "out".to_string(),
+      }
+    }
+  }
+
+  /// Key is the Team's api_id. `standings` must already be sorted, like the output of `compute`.
+  pub fn seeds(standings: &[Standing], teams: &[Team]) -> HashMap<i32, Seed> {
+    let mut seeds: HashMap<i32, Seed> = HashMap::new();
+    let mut division_counts: HashMap<Division, usize> = HashMap::new();
+    for standing in standings {
+      let Some(team) = teams
+        .iter()
+        .find(|team| team.api_id == standing.team_api_id)
+      else {
+        continue;
+      };
+      let count = division_counts.entry(team.division).or_insert(0);
+      if *count < Seed::DIVISION_SPOTS {
+        *count += 1;
+        seeds.insert(team.api_id, Seed::Division(*count));
+      }
+    }
+
+    let mut wild_card_counts: HashMap<Conference, usize> = HashMap::new();
+    for standing in standings {
+      let Some(team) = teams
+        .iter()
+        .find(|team| team.api_id == standing.team_api_id)
+      else {
+        continue;
+      };
+      if seeds.contains_key(&team.api_id) {
+        continue;
+      }
+      let count = wild_card_counts.entry(team.conference).or_insert(0);
+      if *count < Seed::WILD_CARD_SPOTS {
+        *count += 1;
+        seeds.insert(team.api_id, Seed::WildCard(*count));
+      } else {
+        seeds.insert(team.api_id, Seed::Out);
+      }
+    }
+
+    seeds
+  }
+
+  /// The last team in a playoff spot in `conference`, which is its second wild card. Teams are
+  /// identified by their api_id, and `seeds` is the output of `seeds`.
+  pub fn cut_line_team(
+    seeds: &HashMap<i32, Seed>,
+    teams: &[Team],
+    conference: Conference,
+  ) -> Option<i32> {
+    teams
+      .iter()
+      .find(|team| {
+        team.conference == conference
+          && seeds.get(&team.api_id) == Some(&Seed::WildCard(Seed::WILD_CARD_SPOTS))
+      })
+      .map(|team| team.api_id)
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+
+    /// A regulation game, by api_id.
+    fn game(
+      api_id: i32,
+      home_team: i32,
+      away_team: i32,
+      home_score: i32,
+      away_score: i32,
+    ) -> FinishedGame {
+      FinishedGame {
+        api_id,
+        home_team,
+        away_team,
+        home_score,
+        away_score,
+        last_period: LastPeriod::Regulation,
+      }
+    }
+
+    fn ranks(standings: &[Standing]) -> Vec<i32> {
+      standings
+        .iter()
+        .map(|standing| standing.team_api_id)
+        .collect()
+    }
+
+    #[test]
+    fn fixture_standings() {
+      let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/test");
+      let loaded = json::files::load_dir(&dir).unwrap();
+      let [api_response] = loaded.api_responses.as_slice() else {
+        panic!("expected one season in {}", dir.display());
+      };
+      let games = api_response.finished_games().unwrap();
+      let mut team_api_ids: Vec<i32> = games
+        .iter()
+        .flat_map(|game| [game.home_team, game.away_team])
+        .collect();
+      team_api_ids.sort();
+      team_api_ids.dedup();
+
+      let standings = compute(&team_api_ids, &games);
+      assert_eq!(standings.len(), team_api_ids.len());
+      // NYR played every game in the fixtures, so they're first with their real 2023-24 record.
+      let nyr = &standings[0];
+      assert_eq!(nyr.team_api_id, 3);
+      assert_eq!(
+        (
+          nyr.games_played,
+          nyr.wins,
+          nyr.losses,
+          nyr.ot_losses,
+          nyr.points
+        ),
+        (82, 55, 23, 4, 114)
+      );
+      assert_eq!(
+        (nyr.regulation_wins, nyr.goals_for, nyr.goals_against),
+        (43, 282, 229)
+      );
+      assert!(standings
+        .windows(2)
+        .all(|pair| pair[0].points >= pair[1].points));
+    }
+
+    #[test]
+    fn two_team_tie_drops_the_odd_game() {
+      let games = [
+        // 1 hosted two of the three games, so the first one doesn't count, which leaves the
+        // head-to-head even.
+        game(1, 1, 2, 1, 0),
+        game(2, 2, 1, 1, 0),
+        game(3, 1, 2, 1, 0),
+        // Against team 3, which isn't tied, to even out the records but not the goal differential.
+        game(4, 3, 1, 1, 0),
+        game(5, 2, 3, 10, 0),
+      ];
+      let standings = compute(&[1, 2], &games);
+      assert_eq!(standings[0].points, standings[1].points);
+      assert_eq!(ranks(&standings), [2, 1]);
+    }
+
+    #[test]
+    fn three_team_tie_uses_points_percentage() {
+      let games = [
+        // 1 and 2 split four games, and 3 beat 2 in the only other game between the tied teams.
+        // 1 and 2 have more head-to-head points than 3, but 3 has the best percentage.
+        game(1, 1, 2, 1, 0),
+        game(2, 2, 1, 0, 1),
+        game(3, 2, 1, 1, 0),
+        game(4, 1, 2, 0, 1),
+        game(5, 2, 3, 0, 1),
+        // Against team 4, which isn't tied, to even out the records.
+        game(6, 1, 4, 1, 0),
+        game(7, 4, 1, 1, 0),
+        game(8, 2, 4, 1, 0),
+        game(9, 3, 4, 1, 0),
+        game(10, 3, 4, 1, 0),
+        game(11, 4, 3, 1, 0),
+        game(12, 4, 3, 1, 0),
+        game(13, 4, 3, 1, 0),
+      ];
+      let standings = compute(&[1, 2, 3], &games);
+      assert!(standings
+        .iter()
+        .all(|standing| (standing.points, standing.games_played) == (6, 6)));
+      assert_eq!(ranks(&standings), [3, 1, 2]);
+    }
+  }
+}
+
+/// The per-team cumulative points behind the regular season plot. These are kept apart from the UI
+/// so they can be exported.
+pub mod team_series {
+  use crate::*;
+
+  /// One finished game on a team's line.
+  #[derive(Copy, Clone, Debug, serde::Serialize)]
+  pub struct Point {
+    /// 1-based index into the team's schedule.
+    pub game_number: usize,
+    /// The game's date.
+    pub date: NaiveDate,
+    /// Points so far in the chosen points system. Points % uses NHL points.
+    pub points: f32,
+    /// How far ahead of the pace the team is, which is what gets plotted.
+    pub pace_delta: f32,
+    /// The game itself, which the window uses for hover text.
+    #[serde(skip)]
+    pub game: Game,
+  }
+
+  /// One team's line on the regular season plot.
+  #[derive(Clone, Debug, serde::Serialize)]
+  pub struct TeamSeries {
+    /// The team's api id.
+    #[serde(skip)]
+    pub team_api_id: i32,
+    /// The team's abbrev.
+    pub team: String,
+    /// One for each finished game.
+    pub points: Vec<Point>,
+  }
+
+  /// One series for each of `teams`, in the same order.
+  pub fn build(
+    data: &AppData,
+    teams: &[&Team],
+    points_system: PointsSystem,
+    pace: Pace,
+  ) -> Vec<TeamSeries> {
+    let max_points = points_system.max_points();
+    teams
+      .iter()
+      .map(|team| {
+        let pace = pace.points_per_game(points_system, data, team.conference);
+        let mut points = vec![];
+        let mut points_so_far = 0.0;
+        let mut games_played = 0.0;
+        let games = data.games.get(&team.api_id).map_or(&[][..], Vec::as_slice);
+        for (idx, game) in games.iter().enumerate() {
+          if let Some(result) = game.result {
+            points_so_far += points_system.points(&result);
+            games_played += 1.0;
+            let pace_delta = match points_system {
+              PointsSystem::PointsPercentage => (points_so_far / games_played - pace) / max_points,
+              _ => points_so_far - pace * games_played,
+            };
+            points.push(Point {
+              game_number: 1 + idx,
+              date: game.date,
+              points: points_so_far,
+              pace_delta,
+              game: *game,
+            });
+          }
+        }
+        TeamSeries {
+          team_api_id: team.api_id,
+          team: team.abbrev.clone(),
+          points,
+        }
+      })
+      .collect()
+  }
+
+  /// One row per point, with a header row.
+  pub fn to_csv(all_series: &[TeamSeries]) -> String {
+    let mut csv = String::from("team,game_number,date,points,pace_delta\n");
+    for series in all_series {
+      for point in &series.points {
+        csv.push_str(&format!(
+          "{},{},{},{},{}\n",
+          series.team, point.game_number, point.date, point.points, point.pace_delta
+        ));
+      }
+    }
+    csv
+  }
+
+  /// An array of series, each with its team's abbrev and points.
+  pub fn to_json(all_series: &[TeamSeries]) -> Result<String, Error> {
+    Ok(serde_json::to_string_pretty(all_series)? + "\n")
+  }
+}
+
+/// Draws the regular season plot to an image without opening a window, for reports.
+pub mod render {
+  use crate::*;
+  use plotters::coord::Shift;
+  use plotters::prelude::*;
+
+  /// What kind of image `render` writes.
+  #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+  pub enum ImageFormat {
+    /// A bitmap.
+    Png,
+    /// Vector graphics, which scale to any size.
+    Svg,
+  }
+
+  /// How `render` draws the plot.
+  pub struct Options {
+    /// What kind of image to write.
+    pub format: ImageFormat,
+    /// In pixels, including the legend.
+    pub width: u32,
+    /// In pixels.
+    pub height: u32,
+    /// Whether each team's points are plotted by game number or by date.
+    pub x_axis: XAxis,
+    /// Drawn above the plot.
+    pub title: String,
+    /// Usually [`Pace::y_axis_label`].
+    pub y_axis_label: String,
+    /// Whether to draw the zero line, which is the pace unless it's `Pace::Zero`.
+    pub pace_line: bool,
+  }
+
+  /// Room on the right for the team names.
+  const LEGEND_WIDTH: u32 = 80;
+
+  /// Draws `all_series` to an image at `path`, with a legend of their teams from `data`.
+  pub fn render(
+    path: &Path,
+    data: &AppData,
+    all_series: &[team_series::TeamSeries],
+    options: &Options,
+  ) -> Result<(), Error> {
+    register_font()?;
+    let size = (options.width, options.height);
+    match options.format {
+      ImageFormat::Png => draw(
+        BitMapBackend::new(path, size).into_drawing_area(),
+        data,
+        all_series,
+        options,
+      ),
+      ImageFormat::Svg => draw(
+        SVGBackend::new(path, size).into_drawing_area(),
+        data,
+        all_series,
+        options,
+      ),
+    }
+  }
+
+  /// plotters doesn't come with any fonts, so this borrows the one egui draws with.
+  fn register_font() -> Result<(), Error> {
+    let fonts = egui::FontDefinitions::default();
+    match fonts.font_data.get("Ubuntu-Light").map(|data| &data.font) {
+      Some(std::borrow::Cow::Borrowed(bytes)) => {
+        plotters::style::register_font("sans-serif", FontStyle::Normal, bytes)
+          .map_err(|_| Error::Render("egui's default font is invalid".to_string()))
+      }
+      _ => Err(Error::Render("egui's default font is missing".to_string())),
+    }
+  }
+
+  fn draw<B: DrawingBackend>(
+    root: DrawingArea<B, Shift>,
+    data: &AppData,
+    all_series: &[team_series::TeamSeries],
+    options: &Options,
+  ) -> Result<(), Error>
+  where
+    B::ErrorType: 'static,
+  {
+    root.fill(&WHITE)?;
+    let (plot_area, legend_area) =
+      root.split_horizontally(options.width.saturating_sub(LEGEND_WIDTH));
+
+    let x_axis = options.x_axis;
+    let origin = x_axis.origin(&data.games);
+    let mut lines = vec![];
+    for series in all_series {
+      let Some(team) = data
+        .teams
+        .iter()
+        .find(|team| team.api_id == series.team_api_id)
+      else {
+        continue;
+      };
+      let color = RGBColor(team.color.r(), team.color.g(), team.color.b());
+      let points: Vec<(f64, f64)> = std::iter::once((origin, 0.0))
+        .chain(
+          series
+            .points
+            .iter()
+            .map(|point| (x_axis.point_x(point), point.pace_delta as f64)),
+        )
+        .collect();
+      lines.push((&team.abbrev, color, points));
+    }
+
+    let all_points = || lines.iter().flat_map(|(_, _, points)| points);
+    let x_max = all_points().map(|(x, _)| *x).fold(origin + 1.0, f64::max);
+    let y_min = all_points().map(|(_, y)| *y).fold(0.0, f64::min);
+    let y_max = all_points().map(|(_, y)| *y).fold(0.0, f64::max);
+    let margin = match (y_max - y_min) * 0.05 {
+      margin if margin > 0.0 => margin,
+      _ => 1.0,
+    };
+
+    let mut chart = ChartBuilder::on(&plot_area)
+      .caption(&options.title, ("sans-serif", 24))
+      .margin(10)
+      .x_label_area_size(40)
+      .y_label_area_size(60)
+      .build_cartesian_2d(origin..x_max, (y_min - margin)..(y_max + margin))?;
+    let x_label_formatter = |x: &f64| match x_axis {
+      XAxis::Date => XAxis::date(*x)
+        .map(|date| date.format("%b %-d").to_string())
+        .unwrap_or_default(),
+      XAxis::GameNumber => format!("{x:.0}"),
+    };
+    chart
+      .configure_mesh()
+      .x_desc(x_axis.label())
+      .y_desc(&options.y_axis_label)
+      .x_label_formatter(&x_label_formatter)
+      .draw()?;
+
+    if options.pace_line {
+      chart.draw_series(LineSeries::new(
+        [(origin, 0.0), (x_max, 0.0)],
+        BLACK.mix(0.4).stroke_width(2),
+      ))?;
+    }
+    for (_, color, points) in &lines {
+      chart.draw_series(LineSeries::new(points.clone(), color.stroke_width(2)))?;
+    }
+
+    for (idx, (abbrev, color, _)) in lines.iter().enumerate() {
+      let y = 40 + 20 * idx as i32;
+      legend_area.draw(&PathElement::new([(5, y), (25, y)], color.stroke_width(3)))?;
+      legend_area.draw(&Text::new(
+        abbrev.to_string(),
+        (30, y - 7),
+        ("sans-serif", 14),
+      ))?;
+    }
+
+    root.present()?;
+    Ok(())
+  }
+}
+
+/// The SQLite db that schedules and scores are stored in.
+#[derive(Debug)]
+pub struct DB {
+  pub(crate) conn: rusqlite::Connection,
+}
+
+impl DB {
+  fn all<T: for<'a> serde::Deserialize<'a>>(&self, table_name: &str) -> Result<Vec<T>, Error> {
+    // TODO Not sure why this can't use rusqlite's `?1` params - getting SqlInputError with `code: Unknown`.
+    let mut statement = self
+      .conn
+      .prepare(&format!("SELECT * FROM {};", table_name))?;
+    let res = serde_rusqlite::from_rows::<T>(statement.query([])?);
+    Ok(res.collect::<Result<Vec<T>, _>>()?)
+  }
+
+  /// Every row of `scores`.
+  pub fn all_scores(&self) -> Result<Vec<db::Score>, Error> {
+    self.all::<db::Score>("scores")
+  }
+
+  /// Every row of `seasons`.
+  pub fn all_seasons(&self) -> Result<Vec<db::Season>, Error> {
+    self.all::<db::Season>("seasons")
+  }
+
+  /// Every row of `conferences`.
+  pub fn all_conferences(&self) -> Result<Vec<db::Conference>, Error> {
+    self.all::<db::Conference>("conferences")
+  }
+
+  /// Every row of `divisions`.
+  pub fn all_divisions(&self) -> Result<Vec<db::Division>, Error> {
+    self.all::<db::Division>("divisions")
+  }
+
+  /// Every row of `last_period_types`.
+  pub fn all_last_period_types(&self) -> Result<Vec<db::LastPeriodType>, Error> {
+    self.all::<db::LastPeriodType>("last_period_types")
+  }
+
+  fn period_type_ids(&self) -> Result<HashMap<json::PeriodType, i32>, Error> {
+    fn get_period_type_id(db: &DB, name: &str) -> Result<i32, Error> {
+      Ok(db.conn.query_row(
+        "SELECT id FROM last_period_types WHERE name = ?1",
+        [name],
+        |r| r.get(0),
+      )?)
+    }
+    let mut period_type_ids: HashMap<json::PeriodType, i32> = HashMap::new();
+    period_type_ids.insert(
+      json::PeriodType::REG,
+      get_period_type_id(self, "Regulation")?,
+    );
+    period_type_ids.insert(json::PeriodType::SO, get_period_type_id(self, "Shootout")?);
+    period_type_ids.insert(json::PeriodType::OT, get_period_type_id(self, "Overtime")?);
+    Ok(period_type_ids)
+  }
+
+  /// Every row of `teams`.
+  pub fn all_teams(&self) -> Result<Vec<db::Team>, Error> {
+    self.all::<db::Team>("teams")
+  }
+
+  /// The season's db_id, which inserts it if it's not in the db yet.
+  pub fn season_id(&self, season_api_id: i32) -> Result<i32, Error> {
+    self.conn.execute(
+      "INSERT OR IGNORE INTO seasons (api_id) VALUES (?1);",
+      [season_api_id],
+    )?;
+    Ok(self.conn.query_row(
+      "SELECT id FROM seasons WHERE api_id = ?1",
+      [season_api_id],
+      |r| r.get(0),
+    )?)
+  }
+
+  fn series_id(&self, season: &Season, series_status: json::SeriesStatus) -> Result<i32, Error> {
+    let letter = series_status.series_letter.to_string();
+    self.conn.execute(
+      "INSERT OR IGNORE INTO series (season_id, letter, round, needed_to_win) VALUES (?1, ?2, ?3, ?4);",
+      rusqlite::params![
+        season.db_id,
+        letter,
+        series_status.round,
+        series_status.needed_to_win
+      ],
+    )?;
+    Ok(self.conn.query_row(
+      "SELECT id FROM series WHERE season_id = ?1 AND letter = ?2",
+      rusqlite::params![season.db_id, letter],
+      |r| r.get(0),
+    )?)
+  }
+
+  /// The season's playoff series, in bracket order.
+  pub fn all_series(&self, teams: &[Team], season: &Season) -> Result<Vec<Series>, Error> {
+    let scores = self.all_scores()?;
+    let mut series_statement = self
+      .conn
+      .prepare("SELECT * FROM series WHERE series.season_id = ?1 ORDER BY series.letter;")?;
+    let db_series: Vec<db::Series> =
+      serde_rusqlite::from_rows::<db::Series>(series_statement.query([season.db_id])?)
+        .collect::<Result<Vec<db::Series>, _>>()?;
+
+    let mut games_statement = self
+      .conn
+      .prepare("SELECT * FROM games WHERE games.series_id = ?1 ORDER BY games.game_number;")?;
+    let mut all_series = vec![];
+    for series in db_series {
+      let db_games: Vec<db::Game> =
+        serde_rusqlite::from_rows::<db::Game>(games_statement.query([series.id])?)
+          .collect::<Result<Vec<db::Game>, _>>()?;
+      all_series.push(series.db_to_crate(&db_games, &scores, teams)?);
+    }
+    Ok(all_series)
+  }
+
+  fn all_db_games(&self, teams: &[Team], season: &Season) -> Result<DbGamesByTeam, Error> {
+    let mut games: DbGamesByTeam = HashMap::new();
+
+    for team in teams {
+      let mut statement = self
+        .conn
+        .prepare("SELECT * FROM games WHERE (games.home_team_id = ?1 OR games.away_team_id = ?2) AND games.season_id = ?3 ORDER BY games.start_time_utc, games.api_id;")?;
+      let db_games = serde_rusqlite::from_rows::<db::Game>(statement.query([
+        team.db_id,
+        team.db_id,
+        season.db_id,
+      ])?)
+      .collect::<Result<Vec<db::Game>, _>>()?;
+
+      games.insert(team.api_id, db_games);
+    }
+    Ok(games)
+  }
+
+  /// Every regular season game for each of `teams`, in the order they're played.
+  pub fn all_games(&self, teams: &[Team], season: &Season) -> Result<GamesByTeam, Error> {
+    let games_by_team: DbGamesByTeam = self.all_db_games(teams, season)?;
+    let scores = self.all_scores()?;
+    let last_period_types = self.all_last_period_types()?;
+    fn find_score(
+      score_id: i32,
+      scores: &[db::Score],
+      last_period_types: &[db::LastPeriodType],
+    ) -> Result<(db::Score, db::LastPeriodType), Error> {
+      let score = scores
+        .iter()
+        .find(|score| score.id == score_id)
+        .ok_or(Error::MissingRow {
+          table: "scores",
+          id: score_id,
+        })?;
+      let lpt: db::LastPeriodType = last_period_types
+        .iter()
+        .find(|period_type| period_type.id == score.last_period_type_id)
+        .ok_or(Error::MissingRow {
+          table: "last_period_types",
+          id: score.last_period_type_id,
+        })?
+        .clone();
+      Ok((*score, lpt))
+    }
+    let mut all_games = HashMap::new();
+    for team in teams {
+      let team_db_games = &games_by_team[&team.api_id];
+      let mut team_games = vec![];
+      // Playoff games are loaded separately by `all_series`.
+      for db_game in team_db_games
+        .iter()
+        .filter(|db_game| db_game.series_id.is_none())
+      {
+        let game_outcome = match db_game.score_id {
+          Some(score_id) => Some(find_score(score_id, &scores, &last_period_types)?),
+          None => None,
+        };
+        let team_game: Game = db_game.clone().db_to_crate(game_outcome, team, teams)?;
+        team_games.push(team_game)
+      }
+      all_games.insert(team.api_id, team_games);
+    }
+    Ok(all_games)
+  }
+
+  /// The season's finished regular season games, which is what standings are computed from.
+  pub fn finished_games(&self, season: &Season) -> Result<Vec<standings::FinishedGame>, Error> {
+    let last_period_types = self.all_last_period_types()?;
+    let mut statement = self.conn.prepare(
+      "SELECT games.api_id, home_teams.api_id, away_teams.api_id, scores.home, scores.away, scores.last_period_type_id
+       FROM games
+       JOIN scores ON games.score_id = scores.id
+       JOIN teams AS home_teams ON games.home_team_id = home_teams.id
+       JOIN teams AS away_teams ON games.away_team_id = away_teams.id
+       WHERE games.season_id = ?1 AND games.series_id IS NULL
+       ORDER BY games.api_id;",
+    )?;
+    let mut rows = statement.query([season.db_id])?;
+    let mut finished_games = vec![];
+    while let Some(row) = rows.next()? {
+      let last_period_type_id: i32 = row.get(5)?;
+      let last_period = last_period_types
+        .iter()
+        .find(|period_type| period_type.id == last_period_type_id)
+        .ok_or(Error::MissingRow {
+          table: "last_period_types",
+          id: last_period_type_id,
+        })?
+        .db_to_crate()?;
+      finished_games.push(standings::FinishedGame {
+        api_id: row.get(0)?,
+        home_team: row.get(1)?,
+        away_team: row.get(2)?,
+        home_score: row.get(3)?,
+        away_score: row.get(4)?,
+        last_period,
+      });
+    }
+    Ok(finished_games)
+  }
+
+  /// Sorted by rank.
+  pub fn standings(
+    &self,
+    teams: &[Team],
+    season: &Season,
+  ) -> Result<Vec<standings::Standing>, Error> {
+    let team_api_ids: Vec<i32> = teams.iter().map(|team| team.api_id).collect();
+    Ok(standings::compute(
+      &team_api_ids,
+      &self.finished_games(season)?,
+    ))
+  }
+
+  /// Everything there is to show about a season.
+  pub fn app_data(&self, teams: &[Team], season: &Season) -> Result<AppData, Error> {
+    Ok(AppData {
+      games: self.all_games(teams, season)?,
+      series: self.all_series(teams, season)?,
+      standings: self.standings(teams, season)?,
+      teams: teams.to_vec(),
+    })
+  }
+
+  /// Upserts every regular season and playoff game in `api_response`, along with their scores. This
+  /// runs in a single transaction, and it's safe to call repeatedly with the same payload.
+  pub fn insert_schedules(
+    &self,
+    api_response: json::ApiResponse,
+    teams: &[Team],
+  ) -> Result<InsertSummary, Error> {
+    let tx = self.conn.unchecked_transaction()?;
+
+    let season = Season {
+      db_id: self.season_id(api_response.season)?,
+      api_id: api_response.season,
+    };
+    let period_type_ids: HashMap<json::PeriodType, i32> = self.period_type_ids()?;
+
+    let mut insert_game_statement = tx.prepare("INSERT INTO games (api_id, home_team_id, away_team_id, score_id, season_id, series_id, game_number, game_date, start_time_utc, game_state) VALUES (:api_id, :home_team_id, :away_team_id, :score_id, :season_id, :series_id, :game_number, :game_date, :start_time_utc, :game_state);")?;
+    let mut update_game_statement =
+      tx.prepare("UPDATE games SET score_id = :score_id WHERE api_id = :api_id;")?;
+    let mut insert_score_statement = tx.prepare("INSERT INTO scores (home, away, last_period_type_id) VALUES (:home, :away, :last_period_type_id);")?;
+    let mut update_score_statement = tx.prepare("UPDATE scores SET home = :home, away = :away, last_period_type_id = :last_period_type_id WHERE id = :id;")?;
+    let mut reschedule_game_statement = tx.prepare("UPDATE games SET game_date = :game_date, start_time_utc = :start_time_utc WHERE api_id = :api_id;")?;
+    let mut update_state_statement =
+      tx.prepare("UPDATE games SET game_state = :game_state WHERE api_id = :api_id;")?;
+
+    // Key is the game's api_id.
+    let mut existing_scores: HashMap<i32, Option<db::Score>> = HashMap::new();
+    let mut existing_start_times: HashMap<i32, String> = HashMap::new();
+    let mut existing_states: HashMap<i32, String> = HashMap::new();
+    {
+      let mut statement = tx.prepare(
+        "SELECT games.api_id, scores.id, scores.home, scores.away, scores.last_period_type_id, games.start_time_utc, games.game_state
+         FROM games LEFT JOIN scores ON games.score_id = scores.id;",
+      )?;
+      let mut rows = statement.query([])?;
+      while let Some(row) = rows.next()? {
+        let score = match row.get::<_, Option<i32>>(1)? {
+          Some(id) => Some(db::Score {
+            id,
+            home: row.get(2)?,
+            away: row.get(3)?,
+            last_period_type_id: row.get(4)?,
+          }),
+          None => None,
+        };
+        existing_scores.insert(row.get(0)?, score);
+        existing_start_times.insert(row.get(0)?, row.get(5)?);
+        existing_states.insert(row.get(0)?, row.get(6)?);
+      }
+    }
+
+    fn get_db_id(game_team: json::GameTeam, teams: &[Team]) -> Result<i32, Error> {
+      teams
+        .iter()
+        .find(|team| team.api_id == game_team.id)
+        .map(|team| team.db_id)
+        .ok_or(Error::UnknownTeam(game_team.id))
+    }
+
+    fn abbrev(game_team: json::GameTeam, teams: &[Team]) -> String {
+      teams
+        .iter()
+        .find(|team| team.api_id == game_team.id)
+        .map_or_else(|| game_team.id.to_string(), |team| team.abbrev.clone())
+    }
+
+    use crate::json::GameType::*;
+
+    // Each game shows up in both participants' schedules, which might not have been fetched at the
+    // same time, so prefer whichever copy has a result. Key is the game's api_id, which also keeps
+    // the games in schedule order.
+    let mut json_games: BTreeMap<i32, json::Game> = BTreeMap::new();
+    for (_team_api_id, schedule) in api_response.schedules {
+      for json_game in schedule.games {
+        match json_game.get_type()? {
+          Preseason => (),
+          RegularSeason | Playoffs => {
+            let entry = json_games.entry(json_game.id).or_insert(json_game);
+            if entry.game_outcome.is_none() {
+              *entry = json_game;
+            }
+          }
+        }
+      }
+    }
+
+    fn insert_score(
+      statement: &mut rusqlite::Statement,
+      (home, away, last_period_type_id): (i32, i32, i32),
+    ) -> Result<i64, rusqlite::Error> {
+      statement.insert(rusqlite::named_params! {
+        ":home": home,
+        ":away": away,
+        ":last_period_type_id": last_period_type_id,
+      })
+    }
+
+    let mut summary = InsertSummary::default();
+    for json_game in json_games.into_values() {
+      let game_date = json_game.game_date.to_string();
+      let start_time_utc = json_game
+        .start_time_utc
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+      // Postponed games keep their api_id but move to a new date.
+      let rescheduled = existing_start_times
+        .get(&json_game.id)
+        .is_some_and(|existing| *existing != start_time_utc);
+      if rescheduled {
+        reschedule_game_statement.execute(rusqlite::named_params! {
+          ":game_date": game_date,
+          ":start_time_utc": start_time_utc,
+          ":api_id": json_game.id,
+        })?;
+      }
+
+      let new_score = match (
+        json_game.game_outcome,
+        json_game.home_team.score,
+        json_game.away_team.score,
+      ) {
+        (Some(outcome), Some(home), Some(away)) => {
+          Some((home, away, period_type_ids[&outcome.last_period_type]))
+        }
+        _ => None,
+      };
+      let Some(existing_score) = existing_scores.get(&json_game.id) else {
+        let score_id = match new_score {
+          Some(new_score) => Some(insert_score(&mut insert_score_statement, new_score)?),
+          None => None,
+        };
+        let series_id = match (json_game.get_type()?, json_game.series_status) {
+          (Playoffs, Some(series_status)) => Some(self.series_id(&season, series_status)?),
+          _ => None,
+        };
+        insert_game_statement.execute(rusqlite::named_params! {
+          ":api_id": json_game.id,
+          ":home_team_id": get_db_id(json_game.home_team, teams)?,
+          ":away_team_id": get_db_id(json_game.away_team, teams)?,
+          ":score_id": score_id,
+          ":season_id": season.db_id,
+          ":series_id": series_id,
+          ":game_number": json_game.series_status.map(|status| status.game_number_of_series),
+          ":game_date": game_date,
+          ":start_time_utc": start_time_utc,
+          ":game_state": json_game.game_state.as_str(),
+        })?;
+        summary.inserted += 1;
+        continue;
+      };
+
+      let rescored = match (existing_score, new_score) {
+        (None, Some(new_score)) => {
+          let score_id = insert_score(&mut insert_score_statement, new_score)?;
+          update_game_statement.execute(rusqlite::named_params! {
+            ":score_id": score_id,
+            ":api_id": json_game.id,
+          })?;
+          true
+        }
+        (Some(score), Some((home, away, last_period_type_id)))
+          if (score.home, score.away, score.last_period_type_id)
+            != (home, away, last_period_type_id) =>
+        {
+          update_score_statement.execute(rusqlite::named_params! {
+            ":home": home,
+            ":away": away,
+            ":last_period_type_id": last_period_type_id,
+            ":id": score.id,
+          })?;
+          true
+        }
+        _ => false,
+      };
+
+      let old_state = existing_states
+        .get(&json_game.id)
+        .cloned()
+        .unwrap_or_default();
+      // A stale copy of a game, like from a schedule that was fetched earlier, shouldn't undo its
+      // result.
+      let new_state = match json::GameState::parse(&old_state) {
+        Some(state) if state.is_final() && !json_game.game_state.is_final() => state.as_str(),
+        _ => json_game.game_state.as_str(),
+      };
+      if old_state != new_state {
+        update_state_statement.execute(rusqlite::named_params! {
+          ":game_state": new_state,
+          ":api_id": json_game.id,
+        })?;
+      }
+
+      if rescheduled || rescored || old_state != new_state {
+        summary.updated += 1;
+        summary.changes.push(GameChange {
+          date: json_game.game_date,
+          home: abbrev(json_game.home_team, teams),
+          away: abbrev(json_game.away_team, teams),
+          old_state,
+          new_state: new_state.to_string(),
+          score: json_game.home_team.score.zip(json_game.away_team.score),
+        });
+      } else {
+        summary.unchanged += 1;
+      }
+    }
+
+    drop(insert_game_statement);
+    drop(update_game_statement);
+    drop(insert_score_statement);
+    drop(update_score_statement);
+    drop(reschedule_game_statement);
+    drop(update_state_statement);
+    tx.commit()?;
+    Ok(summary)
+  }
+
+  /// Upserts the games on each scoreboard, like `insert_schedules`, and remembers which days are
+  /// final so `stale_days` can skip them next time. Scoreboards also list games that don't belong in
+  /// the standings, like the All-Star game and its non-NHL teams, which are skipped.
+  pub fn insert_scoreboards(
+    &self,
+    season_api_id: i32,
+    scoreboards: Vec<(NaiveDate, json::Scoreboard)>,
+    teams: &[Team],
+  ) -> Result<InsertSummary, Error> {
+    let today = chrono::Utc::now().date_naive();
+    let days: Vec<(NaiveDate, bool)> = scoreboards
+      .iter()
+      .map(|(date, scoreboard)| {
+        // Games can still be added to days that haven't happened yet.
+        let finished = scoreboard
+          .games
+          .iter()
+          .all(|game| game.game_state.is_final());
+        (*date, finished && *date < today)
+      })
+      .collect();
+
+    let is_nhl_team =
+      |game_team: &json::GameTeam| teams.iter().any(|team| team.api_id == game_team.id);
+    let (games, skipped): (Vec<json::Game>, Vec<json::Game>) = scoreboards
+      .into_iter()
+      .flat_map(|(_, scoreboard)| scoreboard.games)
+      .partition(|game| {
+        game.get_type().is_ok() && is_nhl_team(&game.home_team) && is_nhl_team(&game.away_team)
+      });
+    let api_response = json::ApiResponse::from_games(season_api_id, games);
+    let mut summary = self.insert_schedules(api_response, teams)?;
+    summary.skipped = skipped
+      .iter()
+      .filter(|game| game.season == season_api_id)
+      .count() as u32;
+
+    let tx = self.conn.unchecked_transaction()?;
+    {
+      let mut statement = tx.prepare(
+        "INSERT INTO schedule_days (game_date, final) VALUES (?1, ?2)
+         ON CONFLICT (game_date) DO UPDATE SET final = excluded.final;",
+      )?;
+      for (date, is_final) in days {
+        statement.execute(rusqlite::params![date.to_string(), is_final])?;
+      }
+    }
+    tx.commit()?;
+    Ok(summary)
+  }
+
+  /// Each of `dates` that hasn't been fetched since all of its games were finished.
+  pub fn stale_days(
+    &self,
+    dates: impl IntoIterator<Item = NaiveDate>,
+  ) -> Result<Vec<NaiveDate>, Error> {
+    // Games that were rescheduled onto a final day make it stale again.
+    let mut statement = self.conn.prepare(&format!(
+      "SELECT game_date FROM schedule_days WHERE final = 1
+       EXCEPT SELECT game_date FROM games WHERE game_state NOT IN ({});",
+      json::GameState::FINAL_SQL
+    ))?;
+    let final_days: std::collections::HashSet<String> = statement
+      .query_map([], |row| row.get(0))?
+      .collect::<Result<_, _>>()?;
+    Ok(
+      dates
+        .into_iter()
+        .filter(|date| !final_days.contains(&date.to_string()))
+        .collect(),
+    )
+  }
+
+  /// The api_ids of teams with a game in the season that isn't final yet. When there aren't any, like
+  /// before a season or between playoff rounds, this is every team, since new games could show up for
+  /// any of them. A season that's over has nothing left to sync, so that's none of them.
+  pub fn unfinished_teams(&self, teams: &[Team], season_api_id: i32) -> Result<Vec<i32>, Error> {
+    let mut statement = self.conn.prepare(&format!(
+      "SELECT DISTINCT teams.api_id FROM games
+       JOIN seasons ON games.season_id = seasons.id
+       JOIN teams ON teams.id IN (games.home_team_id, games.away_team_id)
+       WHERE seasons.api_id = ?1 AND games.game_state NOT IN ({});",
+      json::GameState::FINAL_SQL
+    ))?;
+    let team_api_ids: Vec<i32> = statement
+      .query_map([season_api_id], |row| row.get(0))?
+      .collect::<Result<_, _>>()?;
+    if !team_api_ids.is_empty() {
+      return Ok(team_api_ids);
+    }
+    let in_progress = match self.season_dates(season_api_id)? {
+      Some((_, last)) => chrono::Utc::now().date_naive() <= season_end(season_api_id, Some(last)),
+      None => true,
+    };
+    if in_progress {
+      Ok(teams.iter().map(|team| team.api_id).collect())
+    } else {
+      Ok(vec![])
+    }
+  }
+
+  /// The days for `sync --league` to fetch, along with the range they're from. `from` defaults to the
+  /// season's first game in the db, or October 1st, and `to` defaults to `today`, but not past the
+  /// end of the season. Days that were already fetched once they were final are left out.
+  pub fn league_days(
+    &self,
+    season_api_id: i32,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    today: NaiveDate,
+  ) -> Result<(NaiveDate, NaiveDate, Vec<NaiveDate>), Error> {
+    let season_dates = self.season_dates(season_api_id)?;
+    let from = from.unwrap_or(match season_dates {
+      Some((first, _)) => first,
+      None => NaiveDate::from_ymd_opt(season_api_id / 10000, 10, 1).unwrap_or_default(),
+    });
+    // Not the last game in the db, since scoreboards only have one day's games.
+    let to = to.unwrap_or(today.min(season_end(
+      season_api_id,
+      season_dates.map(|(_, last)| last),
+    )));
+    let dates = self.stale_days(from.iter_days().take_while(|date| *date <= to))?;
+    Ok((from, to, dates))
+  }
+
+  /// The first and last days with games in the season, if it has any.
+  pub fn season_dates(&self, season_api_id: i32) -> Result<Option<(NaiveDate, NaiveDate)>, Error> {
+    #[derive(serde::Deserialize)]
+    struct SeasonDates {
+      first: Option<NaiveDate>,
+      last: Option<NaiveDate>,
+    }
+
+    let mut statement = self.conn.prepare(
+      "SELECT MIN(games.game_date) AS first, MAX(games.game_date) AS last FROM games
+       JOIN seasons ON games.season_id = seasons.id WHERE seasons.api_id = ?1;",
+    )?;
+    let dates = serde_rusqlite::from_rows::<SeasonDates>(statement.query([season_api_id])?).next();
+    match dates.transpose()? {
+      Some(SeasonDates {
+        first: Some(first),
+        last: Some(last),
+      }) => Ok(Some((first, last))),
+      _ => Ok(None),
+    }
+  }
+
+  fn user_version(&self) -> Result<usize, Error> {
+    let version: i64 = self
+      .conn
+      .query_row("PRAGMA user_version;", [], |r| r.get(0))?;
+    Ok(version as usize)
+  }
+
+  /// Applies each migration that's newer than the db, each in its own transaction.
+  pub fn migrate(&self) -> Result<(), Error> {
+    let mut version = self.user_version()?;
+
+    // Dbs from before migrations existed were created from the first one, which is the original
+    // init.sql, but never set their version.
+    let has_tables: bool = self.conn.query_row(
+      "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'games');",
+      [],
+      |r| r.get(0),
+    )?;
+    if version == 0 && has_tables {
+      self.conn.execute_batch("PRAGMA user_version = 1;")?;
+      version = 1;
+    }
+
+    // Some migrations rebuild a table, which drops it out from under the foreign keys that point to
+    // it. Foreign keys can only be turned off outside of a transaction, so they're checked before
+    // each commit instead.
+    let foreign_keys: bool = self
+      .conn
+      .query_row("PRAGMA foreign_keys;", [], |r| r.get(0))?;
+    self.conn.execute_batch("PRAGMA foreign_keys = OFF;")?;
+    let migrated = MIGRATIONS
+      .iter()
+      .enumerate()
+      .skip(version)
+      .try_for_each(|(idx, migration)| {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute_batch(migration)?;
+        if tx.prepare("PRAGMA foreign_key_check;")?.exists([])? {
+          return Err(Error::BrokenMigration(idx + 1));
+        }
+        tx.execute_batch(&format!("PRAGMA user_version = {};", idx + 1))?;
+        tx.commit()?;
+        log::info!("migrated db to version {}", idx + 1);
+        Ok(())
+      });
+    self
+      .conn
+      .execute_batch(&format!("PRAGMA foreign_keys = {foreign_keys};"))?;
+    migrated
+  }
+
+  /// Opens the db at `db_path`, creating it and its parent directory if needed, and applies any
+  /// migrations it's missing.
+  pub fn initialize(db_path: &Path) -> Result<Self, Error> {
+    if let Some(dir) = db_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+      std::fs::create_dir_all(dir)?;
+    }
+    let conn = rusqlite::Connection::open(db_path)?;
+    let db = Self { conn };
+    db.migrate()?;
+    Ok(db)
+  }
+
+  /// Every season in the db, oldest first.
+  pub fn get_seasons(&self) -> Result<Vec<Season>, Error> {
+    let mut seasons: Vec<Season> = self
+      .all_seasons()?
+      .into_iter()
+      .map(|db_season| db_season.db_to_crate())
+      .collect();
+    seasons.sort_by_key(|season| season.api_id);
+    Ok(seasons)
+  }
+
+  /// Every team, with their division and conference.
+  pub fn get_teams(&self) -> Result<Vec<Team>, Error> {
+    let db_conferences = self.all_conferences()?;
+    let db_divisions = self.all_divisions()?;
+    let db_teams: Vec<db::Team> = self.all_teams()?;
+    db_teams
+      .iter()
+      .map(|db_team| db_team.clone().db_to_crate(&db_divisions, &db_conferences))
+      .collect()
+  }
+}
+
+/// Everything that can go wrong in this crate. None of it panics.
+#[derive(Debug)]
+pub enum Error {
+  /// From SQLite.
+  Sql(rusqlite::Error),
+  /// A row that doesn't fit its struct in [`db`].
+  SqlRow(serde_rusqlite::Error),
+  /// A request to the NHL api that failed, even after retrying.
+  Http(request::Error),
+  /// A response or file that isn't what [`json`] expects.
+  Json(serde_json::Error),
+  /// Reading a file or directory.
+  Io(std::io::Error),
+  /// A conference name in the db that isn't [`Conference`]'s.
+  UnknownConference(String),
+  /// A division name in the db that isn't [`Division`]'s.
+  UnknownDivision(String),
+  /// A gameType that isn't preseason, regular season, or playoffs, like 4 for the All-Star game.
+  UnknownGameType(u8),
+  /// A name in `last_period_types` that isn't [`LastPeriod`]'s.
+  UnknownPeriodType(String),
+  /// Key is the Team's api_id.
+  UnknownTeam(i32),
+  /// A foreign key that doesn't point to anything, like a score with a bad `last_period_type_id`.
+  MissingRow {
+    /// The table without the row.
+    table: &'static str,
+    /// The row's id.
+    id: i32,
+  },
+  /// A schedule source that has nothing for the season, by api_id.
+  MissingSeason(i32),
+  /// A migration, by number, that left foreign keys pointing to rows that don't exist.
+  BrokenMigration(usize),
+  /// From plotters, when drawing an image.
+  Render(String),
+}
+
+impl std::fmt::Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Error::Sql(e) => write!(f, "database error: {e}"),
+      Error::SqlRow(e) => write!(f, "could not read database row: {e}"),
+      Error::Http(e) => write!(f, "error from NHL api: {e}"),
+      Error::Json(e) => write!(f, "could not decode schedule json: {e}"),
+      Error::Io(e) => write!(f, "io error: {e}"),
+      Error::UnknownConference(name) => write!(f, "unknown conference: '{name}'"),
+      Error::UnknownDivision(name) => write!(f, "unknown division: '{name}'"),
+      Error::UnknownGameType(game_type) => write!(f, "unknown gameType: {game_type}"),
+      Error::UnknownPeriodType(name) => write!(f, "unknown last_period_type: '{name}'"),
+      Error::UnknownTeam(api_id) => write!(f, "unknown team with api_id: {api_id}"),
+      Error::MissingRow { table, id } => write!(f, "could not find row in {table} with id: {id}"),
+      Error::MissingSeason(api_id) => write!(f, "no schedules for season {api_id}"),
+      Error::BrokenMigration(version) => {
+        write!(f, "migration {version} left rows with broken foreign keys")
+      }
+      Error::Render(message) => write!(f, "could not draw image: {message}"),
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+impl From<rusqlite::Error> for Error {
+  fn from(e: rusqlite::Error) -> Self {
+    Error::Sql(e)
+  }
+}
+
+impl From<serde_rusqlite::Error> for Error {
+  fn from(e: serde_rusqlite::Error) -> Self {
+    Error::SqlRow(e)
+  }
+}
+
+impl From<request::Error> for Error {
+  fn from(e: request::Error) -> Self {
+    Error::Http(e)
+  }
+}
+
+impl From<serde_json::Error> for Error {
+  fn from(e: serde_json::Error) -> Self {
+    Error::Json(e)
+  }
+}
+
+impl From<std::io::Error> for Error {
+  fn from(e: std::io::Error) -> Self {
+    Error::Io(e)
+  }
+}
+
+impl<E: std::error::Error + Send + Sync> From<plotters::drawing::DrawingAreaErrorKind<E>>
+  for Error
+{
+  fn from(e: plotters::drawing::DrawingAreaErrorKind<E>) -> Self {
+    Error::Render(e.to_string())
+  }
+}
+
+/// Key is the Team's api_id.
+pub type GamesByTeam = HashMap<i32, Vec<Game>>;
+type DbGamesByTeam = HashMap<i32, Vec<db::Game>>;
+
+/// A team's division, which decides the top three seeds in each.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Division {
+  /// Central.
+  Central,
+  /// Pacific.
+  Pacific,
+  /// Atlantic.
+  Atlantic,
+  /// Metropolitan.
+  Metropolitan,
+}
+
+impl Division {
+  /// East, then West.
+  pub const ALL: [Division; 4] = [
+    Division::Metropolitan,
+    Division::Atlantic,
+    Division::Central,
+    Division::Pacific,
+  ];
+
+  /// A short name, like "Metro".
+  pub fn label(self) -> &'static str {
+    match self {
+      Division::Metropolitan => "Metro",
+      Division::Atlantic => "Atlantic",
+      Division::Central => "Central",
+      Division::Pacific => "Pacific",
+    }
+  }
+}
+
+/// Each conference's top eight teams make the playoffs.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Conference {
+  /// Eastern.
+  Eastern,
+  /// Western.
+  Western,
+}
+
+impl Conference {
+  /// Every conference.
+  pub const ALL: [Conference; 2] = [Conference::Eastern, Conference::Western];
+
+  /// A short name, like "East".
+  pub fn label(self) -> &'static str {
+    match self {
+      Conference::Eastern => "East",
+      Conference::Western => "West",
+    }
+  }
+}
+
+/// A season that's in the db.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Season {
+  /// The season's id in the db.
+  pub db_id: i32,
+  /// Seasons are identified by both years, like 20232024.
+  pub api_id: i32,
+}
+
+impl Season {
+  /// Like "2023-24".
+  pub fn label(&self) -> String {
+    format!("{}-{:02}", self.api_id / 10000, self.api_id % 100)
+  }
+}
+
+/// Everything about a team that's needed to plot and rank it.
+#[derive(Clone, Debug)]
+pub struct Team {
+  /// The team's id in the db.
+  pub db_id: i32,
+  /// The NHL api's id for the team.
+  pub api_id: i32,
+  /// Its lines are drawn in this.
+  pub color: Color32,
+  /// Like "NYR".
+  pub abbrev: String,
+  /// The team's division.
+  pub division: Division,
+  /// The team's conference.
+  pub conference: Conference,
+}
+
+/// The period a game ended in.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LastPeriod {
+  /// Over in 60 minutes.
+  Regulation,
+  /// Over in overtime.
+  Overtime,
+  /// Over after a shootout.
+  Shootout,
+}
+
+/// How a finished game went, from one team's point of view.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Decision {
+  /// A win in regulation, overtime, or a shootout.
+  W,
+  /// A regulation loss.
+  L,
+  /// An overtime loss.
+  OTL,
+  /// A shootout loss.
+  SOL,
+}
+
+/// A finished game, from one team's point of view. This is the only place that knows how a score
+/// turns into a win or a loss, so everything else should go through `decision()`.
+#[derive(Copy, Clone, Debug)]
+pub struct GameResult {
+  /// Goals scored.
+  pub goals_for: i32,
+  /// Goals allowed.
+  pub goals_against: i32,
+  /// Whether it was over in regulation, overtime, or a shootout.
+  pub last_period: LastPeriod,
+}
+
+impl GameResult {
+  /// From the team's goals, the other team's goals, and when the game ended.
+  pub fn new(goals_for: i32, goals_against: i32, last_period: LastPeriod) -> Self {
+    Self {
+      goals_for,
+      goals_against,
+      last_period,
+    }
+  }
+
+  /// W, L, OTL, or SOL.
+  pub fn decision(&self) -> Decision {
+    if self.goals_for > self.goals_against {
+      Decision::W
+    } else {
+      match self.last_period {
+        LastPeriod::Regulation => Decision::L,
+        LastPeriod::Overtime => Decision::OTL,
+        LastPeriod::Shootout => Decision::SOL,
+      }
+    }
+  }
+}
+
+/// How many points each result is worth.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum PointsSystem {
+  /// 2 for a win, 1 for an overtime or shootout loss.
+  Nhl,
+  /// 3 for a regulation win, 2 for an overtime or shootout win, 1 for an overtime or shootout loss.
+  Iihf,
+  /// 1 for any win.
+  Wins,
+  /// NHL points divided by the points that were available.
+  PointsPercentage,
+}
+
+impl PointsSystem {
+  /// Every points system, in the order they're listed.
+  pub const ALL: [PointsSystem; 4] = [
+    PointsSystem::Nhl,
+    PointsSystem::Iihf,
+    PointsSystem::Wins,
+    PointsSystem::PointsPercentage,
+  ];
+
+  /// How it's named on the command line.
+  pub fn key(self) -> &'static str {
+    match self {
+      PointsSystem::Nhl => "nhl",
+      PointsSystem::Iihf => "iihf",
+      PointsSystem::Wins => "wins",
+      PointsSystem::PointsPercentage => "pct",
+    }
+  }
+
+  /// How it's named in the window.
+  pub fn label(self) -> &'static str {
+    match self {
+      PointsSystem::Nhl => "NHL (2-1-0)",
+      PointsSystem::Iihf => "IIHF (3-2-1-0)",
+      PointsSystem::Wins => "wins",
+      PointsSystem::PointsPercentage => "points %",
+    }
+  }
+
+  /// What `result` is worth.
+  pub fn points(self, result: &GameResult) -> f32 {
+    match (self, result.decision()) {
+      (PointsSystem::Nhl | PointsSystem::PointsPercentage, Decision::W) => 2.0,
+      (PointsSystem::Nhl | PointsSystem::PointsPercentage, Decision::OTL | Decision::SOL) => 1.0,
+      (PointsSystem::Iihf, Decision::W) => {
+        if result.last_period == LastPeriod::Regulation {
+          3.0
+        } else {
+          2.0
+        }
+      }
+      (PointsSystem::Iihf, Decision::OTL | Decision::SOL) => 1.0,
+      (PointsSystem::Wins, Decision::W) => 1.0,
+      (_, _) => 0.0,
+    }
+  }
+
+  /// The most points a team can get from one game.
+  pub fn max_points(self) -> f32 {
+    match self {
+      PointsSystem::Nhl | PointsSystem::PointsPercentage => 2.0,
+      PointsSystem::Iihf => 3.0,
+      PointsSystem::Wins => 1.0,
+    }
+  }
+
+  /// What points in this system are called, like "wins".
+  pub fn units(self) -> &'static str {
+    match self {
+      PointsSystem::PointsPercentage => "points %",
+      PointsSystem::Wins => "wins",
+      PointsSystem::Nhl | PointsSystem::Iihf => "points",
+    }
+  }
+
+  /// Returns None for teams that haven't finished a game yet.
+  pub fn points_per_game(self, games: &[Game]) -> Option<f32> {
+    let results: Vec<GameResult> = games.iter().filter_map(|game| game.result).collect();
+    if results.is_empty() {
+      None
+    } else {
+      let points: f32 = results.iter().map(|result| self.points(result)).sum();
+      Some(points / results.len() as f32)
+    }
+  }
+}
+
+/// The baseline that gets subtracted from each game, so a line going up means a team is beating the
+/// pace.
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Pace {
+  /// A fraction of the most points available per game, so .5 is a .500 pace in any points system.
+  Fraction(f32),
+  /// The points per game of the last team in a playoff spot in each team's conference, which is
+  /// its second wild card.
+  PlayoffCutLine,
+  /// The points per game of every team in the league.
+  LeagueAverage,
+  /// Raw cumulative points.
+  Zero,
+}
+
+impl Pace {
+  /// A short description, like "league average".
+  pub fn label(self) -> &'static str {
+    match self {
+      Pace::Fraction(_) => "points per game",
+      Pace::PlayoffCutLine => "playoff cut line",
+      Pace::LeagueAverage => "league average",
+      Pace::Zero => "none",
+    }
+  }
+
+  /// The pace for teams in `conference`. Only the playoff cut line differs between conferences.
+  pub fn points_per_game(
+    self,
+    points_system: PointsSystem,
+    data: &AppData,
+    conference: Conference,
+  ) -> f32 {
+    match self {
+      Pace::Fraction(fraction) => fraction * points_system.max_points(),
+      Pace::PlayoffCutLine => {
+        let seeds = standings::seeds(&data.standings, &data.teams);
+        standings::cut_line_team(&seeds, &data.teams, conference)
+          .and_then(|team_api_id| data.games.get(&team_api_id))
+          .and_then(|games| points_system.points_per_game(games))
+          .unwrap_or(0.0)
+      }
+      Pace::LeagueAverage => {
+        let all_games: Vec<Game> = data.games.values().flatten().copied().collect();
+        points_system.points_per_game(&all_games).unwrap_or(0.0)
+      }
+      Pace::Zero => 0.0,
+    }
+  }
+
+  /// Like "points above pace", or just "points" when there isn't one.
+  pub fn y_axis_label(self, points_system: PointsSystem) -> String {
+    match self {
+      Pace::Zero => points_system.units().to_string(),
+      Pace::Fraction(_) | Pace::PlayoffCutLine | Pace::LeagueAverage => {
+        format!("{} above pace", points_system.units())
+      }
+    }
+  }
+}
+
+/// What the regular season plot's x axis shows.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum XAxis {
+  /// Each team's first game is 1, and so on.
+  GameNumber,
+  /// Days since the common era, so every team's lines line up in real time.
+  Date,
+}
+
+impl XAxis {
+  /// Every x axis.
+  pub const ALL: [XAxis; 2] = [XAxis::GameNumber, XAxis::Date];
+
+  /// How it's named in the window and on the rendered image.
+  pub fn label(self) -> &'static str {
+    match self {
+      XAxis::GameNumber => "game number",
+      XAxis::Date => "date",
+    }
+  }
+
+  /// Where every line starts: before the first game, or the day before opening night.
+  pub fn origin(self, games: &GamesByTeam) -> f64 {
+    match self {
+      XAxis::GameNumber => 0.0,
+      XAxis::Date => games
+        .values()
+        .flatten()
+        .map(|game| game.date.num_days_from_ce() - 1)
+        .min()
+        .unwrap_or(0) as f64,
+    }
+  }
+
+  /// Where `point` goes on this axis.
+  pub fn point_x(self, point: &team_series::Point) -> f64 {
+    self.x(point.game_number - 1, &point.game)
+  }
+
+  /// `idx` is the game's index in its team's schedule.
+  pub fn x(self, idx: usize, game: &Game) -> f64 {
+    match self {
+      XAxis::GameNumber => (1 + idx) as f64,
+      XAxis::Date => game.date.num_days_from_ce() as f64,
+    }
+  }
+
+  /// The date at `x` on the date axis, if it's a real date.
+  pub fn date(x: f64) -> Option<NaiveDate> {
+    NaiveDate::from_num_days_from_ce_opt(x.round() as i32)
+  }
+}
+
+/// One regular season game, from one team's point of view.
+#[derive(Copy, Clone, Debug)]
+pub struct Game {
+  /// The game's date.
+  pub date: NaiveDate,
+  /// The other team's api_id.
+  pub opponent: i32,
+  /// Whether the team was the home team.
+  pub home: bool,
+  /// This is None until the game is finished.
+  pub result: Option<GameResult>,
+}
+
+impl Game {
+  /// Like "Oct 12, 2023 vs BOS: W 5-3 (OT)".
+  pub fn description(&self, teams: &[Team]) -> String {
+    let opponent = teams
+      .iter()
+      .find(|team| team.api_id == self.opponent)
+      .map_or("?", |team| team.abbrev.as_str());
+    let matchup = format!(
+      "{} {} {opponent}",
+      self.date.format("%b %-d, %Y"),
+      if self.home { "vs" } else { "@" },
+    );
+    match self.result {
+      Some(result) => {
+        let decision = match result.decision() {
+          Decision::W => "W",
+          Decision::L => "L",
+          Decision::OTL | Decision::SOL => "OTL",
+        };
+        let last_period = match result.last_period {
+          LastPeriod::Regulation => "",
+          LastPeriod::Overtime => " (OT)",
+          LastPeriod::Shootout => " (SO)",
+        };
+        format!(
+          "{matchup}: {decision} {}-{}{last_period}",
+          result.goals_for, result.goals_against
+        )
+      }
+      None => matchup,
+    }
+  }
+}
+
+/// Teams are identified by their api_id.
+#[derive(Copy, Clone, Debug)]
+pub struct PlayoffGame {
+  /// Which game of the series this is, starting at 1.
+  pub game_number: i32,
+  /// The home team.
+  pub home_team: i32,
+  /// The away team.
+  pub away_team: i32,
+  /// None until the game is finished.
+  pub winner: Option<i32>,
+}
+
+/// A playoff series between two teams.
+#[derive(Clone, Debug)]
+pub struct Series {
+  /// Series are lettered in bracket order: A-H in the first round, through O for the final.
+  pub letter: char,
+  /// 1 for the first round, up to 4 for the final.
+  pub round: i32,
+  /// How many wins it takes to take the series.
+  pub needed_to_win: i32,
+  /// In game order.
+  pub games: Vec<PlayoffGame>,
+}
+
+impl Series {
+  /// The higher seed has home ice, so they host the first game.
+  pub fn top_seed(&self) -> Option<i32> {
+    self.games.first().map(|game| game.home_team)
+  }
+
+  /// The lower seed, who starts on the road.
+  pub fn bottom_seed(&self) -> Option<i32> {
+    self.games.first().map(|game| game.away_team)
+  }
+
+  /// How many games `team_api_id` has won in the series.
+  pub fn wins(&self, team_api_id: i32) -> i32 {
+    self
+      .games
+      .iter()
+      .filter(|game| game.winner == Some(team_api_id))
+      .count() as i32
+  }
+
+  /// The team that won the series, once one has.
+  pub fn winner(&self) -> Option<i32> {
+    [self.top_seed(), self.bottom_seed()]
+      .into_iter()
+      .flatten()
+      .find(|team_api_id| self.wins(*team_api_id) >= self.needed_to_win)
+  }
+}
+
+/// A season's games, playoff series, and standings.
+#[derive(Clone, Debug)]
+pub struct AppData {
+  /// Each team's regular season games, in order.
+  pub games: GamesByTeam,
+  /// Each playoff series in the season.
+  pub series: Vec<Series>,
+  /// Sorted by rank.
+  pub standings: Vec<standings::Standing>,
+  /// Every team, including ones without games.
+  pub teams: Vec<Team>,
+}
+
+impl AppData {
+  /// No games, for a season that hasn't been synced yet or a db that couldn't be read.
+  pub fn empty(teams: Vec<Team>) -> Self {
+    Self {
+      games: HashMap::new(),
+      series: vec![],
+      standings: vec![],
+      teams,
+    }
+  }
+}
+
+/// Seasons end by summer, unless the season's `last` game already went later than that.
+fn season_end(season_api_id: i32, last: Option<NaiveDate>) -> NaiveDate {
+  let end = NaiveDate::from_ymd_opt(season_api_id / 10000 + 1, 6, 30).unwrap_or_default();
+  last.map_or(end, |last| last.max(end))
+}
+
+/// How many games `DB::insert_schedules` inserted, updated, and left alone.
+#[derive(Clone, Debug, Default)]
+pub struct InsertSummary {
+  /// Games that weren't in the db yet.
+  pub inserted: u32,
+  /// Games whose state, score, or start time changed. There's one [`GameChange`] for each.
+  pub updated: u32,
+  /// Games that were already up to date.
+  pub unchanged: u32,
+  /// Games that were left out of the db, like the All-Star game.
+  pub skipped: u32,
+  /// One for each updated game.
+  pub changes: Vec<GameChange>,
+}
+
+/// A game that was already in the db, but whose state, score, or start time changed.
+#[derive(Clone, Debug)]
+pub struct GameChange {
+  /// The game's date, at the venue.
+  pub date: NaiveDate,
+  /// The home team's abbrev.
+  pub home: String,
+  /// The away team's abbrev.
+  pub away: String,
+  /// The game's gameState before, like "FUT".
+  pub old_state: String,
+  /// The game's gameState after, like "OFF".
+  pub new_state: String,
+  /// Home and away goals, once the game has started.
+  pub score: Option<(i32, i32)>,
+}
+
+impl std::fmt::Display for GameChange {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.score {
+      Some((home, away)) => write!(
+        f,
+        "{} {} {away} @ {} {home}: ",
+        self.date, self.away, self.home
+      )?,
+      None => write!(f, "{} {} @ {}: ", self.date, self.away, self.home)?,
+    }
+    if self.old_state == self.new_state {
+      write!(f, "{}", self.new_state)
+    } else {
+      write!(f, "{} -> {}", self.old_state, self.new_state)
+    }
+  }
+}
+
+impl std::fmt::Display for InsertSummary {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{} inserted, {} updated, {} unchanged",
+      self.inserted, self.updated, self.unchanged
+    )?;
+    if self.skipped > 0 {
+      write!(f, ", {} skipped", self.skipped)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn test_dir() -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("data/test")
+  }
+
+  fn memory_db() -> DB {
+    let db = DB {
+      conn: rusqlite::Connection::open_in_memory().unwrap(),
+    };
+    db.migrate().unwrap();
+    db
+  }
+
+  /// What `sync` does, but for every team.
+  fn sync(db: &DB, source: &dyn source::ScheduleSource) -> InsertSummary {
+    let teams = db.get_teams().unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let api_response = runtime
+      .block_on(source.load_season(&teams, 20232024))
+      .unwrap();
+    db.insert_schedules(api_response, &teams).unwrap()
+  }
+
+  fn counts(summary: &InsertSummary) -> (u32, u32, u32) {
+    (summary.inserted, summary.updated, summary.unchanged)
+  }
+
+  #[test]
+  fn syncs_offline() {
+    let db = memory_db();
+    // NYR's regular season and playoff games, plus EDM's, but not their preseason games.
+    let files = source::Files { dir: test_dir() };
+    assert_eq!(counts(&sync(&db, &files)), (95, 0, 0));
+
+    let loaded = json::files::load_dir(&test_dir()).unwrap();
+    let fixture = source::Fixture::new(loaded.api_responses.clone());
+    assert_eq!(counts(&sync(&db, &fixture)), (0, 0, 95));
+
+    let mut api_response = loaded.api_responses[0].clone();
+    let nyr_games = &mut api_response.schedules.get_mut(&3).unwrap().games;
+    let finished_game = nyr_games
+      .iter_mut()
+      .find(|game| game.game_type == 2 && game.game_outcome.is_some())
+      .unwrap();
+    finished_game.home_team.score = finished_game.home_team.score.map(|score| score + 1);
+    let summary = sync(&db, &source::Fixture::new([api_response]));
+    assert_eq!(counts(&summary), (0, 1, 94));
+    assert_eq!(summary.changes.len(), 1);
+  }
+
+  #[test]
+  fn league_days_go_past_the_last_game() {
+    let db = memory_db();
+    let teams = db.get_teams().unwrap();
+    let loaded = json::files::load_dir(&test_dir()).unwrap();
+    let game = loaded.api_responses[0].schedules[&3]
+      .games
+      .iter()
+      .find(|game| game.game_type == 2 && game.game_outcome.is_some())
+      .copied()
+      .unwrap();
+
+    let (from, to, dates) = db
+      .league_days(20232024, None, None, game.game_date)
+      .unwrap();
+    assert_eq!(from, NaiveDate::from_ymd_opt(2023, 10, 1).unwrap());
+    assert_eq!(to, game.game_date);
+    let scoreboards = dates
+      .iter()
+      .map(|date| {
+        let games = if *date == game.game_date {
+          vec![game]
+        } else {
+          vec![]
+        };
+        (*date, json::Scoreboard { games })
+      })
+      .collect();
+    db.insert_scoreboards(20232024, scoreboards, &teams)
+      .unwrap();
+
+    // A week later, only the new days need fetching.
+    let today = game.game_date + chrono::Days::new(7);
+    let (_, to, dates) = db.league_days(20232024, None, None, today).unwrap();
+    assert_eq!(to, today);
+    let new_days: Vec<NaiveDate> = game
+      .game_date
+      .succ_opt()
+      .unwrap()
+      .iter_days()
+      .take(7)
+      .collect();
+    assert_eq!(dates, new_days);
+  }
+
+  #[test]
+  fn unfinished_teams() {
+    let db = memory_db();
+    let teams = db.get_teams().unwrap();
+    // Nothing's been synced for the season yet.
+    assert_eq!(
+      db.unfinished_teams(&teams, 20232024).unwrap().len(),
+      teams.len()
+    );
+
+    sync(&db, &source::Files { dir: test_dir() });
+    let mut unfinished = db.unfinished_teams(&teams, 20232024).unwrap();
+    unfinished.sort();
+    // The fixture was fetched before the end of NYR and CAR's playoff series.
+    assert_eq!(unfinished, [3, 12]);
+
+    db.conn
+      .execute("UPDATE games SET game_state = 'OFF';", [])
+      .unwrap();
+    assert!(db.unfinished_teams(&teams, 20232024).unwrap().is_empty());
+  }
+
+  #[test]
+  fn parses_game_states() {
+    let parse = |text: &str| serde_json::from_str::<json::GameState>(text).unwrap();
+    assert!(parse("\"OVER\"").is_final());
+    assert_eq!(parse("\"NEW\""), json::GameState::Unknown);
+    assert!(!json::GameState::Unknown.is_final());
+    for state in json::GameState::ALL {
+      assert_eq!(json::GameState::parse(state.as_str()), Some(state));
+    }
+  }
+
+  #[test]
+  fn skips_all_star_games() {
+    let db = memory_db();
+    let teams = db.get_teams().unwrap();
+    let loaded = json::files::load_dir(&test_dir()).unwrap();
+    let game = loaded.api_responses[0].schedules[&3]
+      .games
+      .iter()
+      .find(|game| game.game_type == 2)
+      .copied()
+      .unwrap();
+    let mut all_star_game = game;
+    all_star_game.id += 1_000_000;
+    all_star_game.game_type = 4;
+    let mut non_nhl_game = game;
+    non_nhl_game.id += 2_000_000;
+    non_nhl_game.away_team.id = 7460;
+
+    let scoreboard = json::Scoreboard {
+      games: vec![game, all_star_game, non_nhl_game],
+    };
+    let summary = db
+      .insert_scoreboards(20232024, vec![(game.game_date, scoreboard)], &teams)
+      .unwrap();
+    assert_eq!(counts(&summary), (1, 0, 0));
+    assert_eq!(summary.skipped, 2);
+  }
+
+  /// A db like the ones from before migrations, made by the original init.sql, with one finished
+  /// game.
+  fn baseline_db() -> DB {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(MIGRATIONS[0]).unwrap();
+    conn
+      .execute_batch(
+        "INSERT INTO scores (home, away, last_period_type_id) VALUES (3, 2, 1);
+         INSERT INTO games (api_id, home_team_id, away_team_id, score_id) VALUES (
+           2023020005,
+           (SELECT id FROM teams WHERE abbrev = 'NYR'),
+           (SELECT id FROM teams WHERE abbrev = 'BUF'),
+           1
+         );",
+      )
+      .unwrap();
+    DB { conn }
+  }
+
+  #[test]
+  fn migrates_baseline_db() {
+    let db = baseline_db();
+    db.migrate().unwrap();
+    assert_eq!(db.user_version().unwrap(), MIGRATIONS.len());
+
+    let teams = db.get_teams().unwrap();
+    let nyr = teams.iter().find(|team| team.abbrev == "NYR").unwrap();
+    assert_eq!(nyr.conference, Conference::Eastern);
+    let season = *db.get_seasons().unwrap().last().unwrap();
+    assert_eq!(season.api_id, 20232024);
+
+    let data = db.app_data(&teams, &season).unwrap();
+    let result = data.games[&nyr.api_id][0].result.unwrap();
+    assert_eq!((result.goals_for, result.goals_against), (3, 2));
+    assert_eq!(data.standings[0].team_api_id, nyr.api_id);
+    assert_eq!(data.standings[0].points, 2);
+    // The game doesn't have its date yet, so syncing should fetch it again.
+    let mut unfinished = db.unfinished_teams(&teams, season.api_id).unwrap();
+    unfinished.sort();
+    assert_eq!(unfinished, [3, 7]);
+  }
+}
@@ -1,14 +1,218 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{Datelike, NaiveDate};
 
 use eframe::egui;
 use egui::{Color32, RichText};
 use egui_plot::{Legend, Line, PlotPoint, PlotPoints};
 
-use reqwest as request;
+use hockey_plots::*;
+
+const DB_PATH: &str = "../data/hockeyplots.db";
+const DATA_DIR: &str = "../data";
+
+// Where the db and schedule dumps live, and where syncing gets schedules from. Each setting comes
+// from the first of: a command line flag (`--db`, `--data-dir`, `--source`), an environment variable
+// (`HOCKEY_PLOTS_DB`, `HOCKEY_PLOTS_DATA_DIR`, `HOCKEY_PLOTS_SOURCE`), the config file
+// (`~/.config/hockey-plots/config.json`), or the defaults above.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default)]
+struct Config {
+  db_path: PathBuf,
+  data_dir: PathBuf,
+  // "api" for the NHL api, or else a directory of schedule json dumps.
+  source: String,
+  api: ApiConfig,
+}
+
+// How hard the NHL api source is allowed to hit the api. These are only read from the config file,
+// like `"api": {"requests_per_second": 2}`.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default)]
+struct ApiConfig {
+  concurrency: usize,
+  requests_per_second: f64,
+  timeout_secs: f64,
+  retries: u32,
+  backoff_secs: f64,
+}
+
+impl Default for ApiConfig {
+  fn default() -> Self {
+    let nhl_api = source::NhlApi::default();
+    Self {
+      concurrency: nhl_api.concurrency,
+      requests_per_second: nhl_api.requests_per_second,
+      timeout_secs: nhl_api.timeout.as_secs_f64(),
+      retries: nhl_api.retries,
+      backoff_secs: nhl_api.backoff.as_secs_f64(),
+    }
+  }
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      db_path: PathBuf::from(DB_PATH),
+      data_dir: PathBuf::from(DATA_DIR),
+      source: "api".to_string(),
+      api: ApiConfig::default(),
+    }
+  }
+}
+
+// What the command line can fail with: anything from the library, or a bad command line or config.
+#[derive(Debug)]
+enum CliError {
+  Lib(Error),
+  // A bad command line flag or config file.
+  Config(String),
+  // A command line that doesn't match the usage.
+  Usage(String),
+}
+
+impl std::fmt::Display for CliError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      CliError::Lib(e) => write!(f, "{e}"),
+      CliError::Config(message) => write!(f, "config error: {message}"),
+      CliError::Usage(message) => write!(f, "{message}"),
+    }
+  }
+}
+
+impl From<Error> for CliError {
+  fn from(e: Error) -> Self {
+    CliError::Lib(e)
+  }
+}
+
+impl From<std::io::Error> for CliError {
+  fn from(e: std::io::Error) -> Self {
+    CliError::Lib(e.into())
+  }
+}
+
+impl From<serde_json::Error> for CliError {
+  fn from(e: serde_json::Error) -> Self {
+    CliError::Lib(e.into())
+  }
+}
+
+impl Config {
+  fn file_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+      Some(dir) => PathBuf::from(dir),
+      None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("hockey-plots").join("config.json"))
+  }
+
+  // Removes the flags it understands, along with their values, from `args`.
+  fn load(args: &mut Vec<String>) -> Result<Self, CliError> {
+    let mut config = match Self::file_path().filter(|path| path.exists()) {
+      Some(path) => {
+        let contents = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&contents)
+          .map_err(|e| CliError::Config(format!("could not read {}: {e}", path.display())))?
+      }
+      None => Self::default(),
+    };
+
+    if let Some(db_path) = std::env::var_os("HOCKEY_PLOTS_DB") {
+      config.db_path = db_path.into();
+    }
+    if let Some(data_dir) = std::env::var_os("HOCKEY_PLOTS_DATA_DIR") {
+      config.data_dir = data_dir.into();
+    }
+    if let Some(source) = std::env::var_os("HOCKEY_PLOTS_SOURCE") {
+      config.source = source.to_string_lossy().into_owned();
+    }
+
+    let mut idx = 1;
+    while idx < args.len() {
+      if !matches!(args[idx].as_str(), "--db" | "--data-dir" | "--source") {
+        idx += 1;
+        continue;
+      }
+      let flag = args.remove(idx);
+      if idx == args.len() {
+        return Err(CliError::Config(format!("{flag} needs a value")));
+      }
+      let value = args.remove(idx);
+      match flag.as_str() {
+        "--db" => config.db_path = value.into(),
+        "--data-dir" => config.data_dir = value.into(),
+        _ => config.source = value,
+      }
+    }
+
+    let api = &config.api;
+    if api.concurrency == 0 || api.requests_per_second <= 0.0 {
+      return Err(CliError::Config(
+        "api.concurrency and api.requests_per_second should be more than 0".to_string(),
+      ));
+    }
+    for (name, secs) in [
+      ("timeout_secs", api.timeout_secs),
+      ("backoff_secs", api.backoff_secs),
+    ] {
+      if Duration::try_from_secs_f64(secs).is_err() {
+        return Err(CliError::Config(format!(
+          "api.{name} should be a number of seconds, not {secs}"
+        )));
+      }
+    }
+    Ok(config)
+  }
+
+  fn nhl_api(&self) -> source::NhlApi {
+    source::NhlApi {
+      concurrency: self.api.concurrency,
+      requests_per_second: self.api.requests_per_second,
+      timeout: Duration::from_secs_f64(self.api.timeout_secs),
+      retries: self.api.retries,
+      backoff: Duration::from_secs_f64(self.api.backoff_secs),
+    }
+  }
+
+  fn schedule_source(&self) -> std::sync::Arc<dyn source::ScheduleSource> {
+    match self.source.as_str() {
+      "api" => std::sync::Arc::new(self.nhl_api()),
+      dir => std::sync::Arc::new(source::Files { dir: dir.into() }),
+    }
+  }
+}
 
 fn main() -> Result<(), eframe::Error> {
   env_logger::init();
 
+  let mut args: Vec<String> = std::env::args().collect();
+  let config = match Config::load(&mut args) {
+    Ok(config) => config,
+    Err(e) => {
+      eprintln!("{e}");
+      std::process::exit(1);
+    }
+  };
+
+  match args.get(1).map(String::as_str) {
+    None | Some("gui") => (),
+    Some(command) => match cli::run(command, &args[2..], &config) {
+      Ok(()) => return Ok(()),
+      Err(e @ CliError::Usage(_)) => {
+        eprintln!("{e}\n\n{}", cli::USAGE);
+        std::process::exit(2);
+      }
+      Err(e) => {
+        eprintln!("{command} failed: {e}");
+        std::process::exit(1);
+      }
+    },
+  }
+
   let options = eframe::NativeOptions {
     viewport: egui::ViewportBuilder::default().with_inner_size([1280.0, 960.0]),
     ..Default::default()
@@ -30,689 +234,1511 @@ fn main() -> Result<(), eframe::Error> {
   eframe::run_native(
     "hockey plots",
     options,
-    Box::new(|_cc| Box::<App>::default()),
+    Box::new(|cc| Box::new(App::new(cc, config))),
   )
 }
 
-mod db {
-  #[derive(Clone, Debug, serde::Deserialize)]
-  pub struct Division {
-    pub id: i32,
-    pub name: String,
-  }
-
-  #[derive(Clone, Debug, serde::Deserialize)]
-  pub struct LastPeriodType {
-    pub id: i32,
-    pub name: String,
-  }
-
-  #[derive(Clone, Debug, serde::Deserialize)]
-  pub struct Team {
-    pub id: i32,
-    pub api_id: i32,
-    pub r: i32,
-    pub g: i32,
-    pub b: i32,
-    pub abbrev: String,
-    pub division_id: i32,
-  }
-
-  impl Team {
-    pub fn db_to_crate(self, db_divisions: &[Division]) -> crate::Team {
-      fn find_division(id: i32, db_divisions: &[Division]) -> crate::Division {
-        let db_division = db_divisions
-          .iter()
-          .find(|db_division| db_division.id == id)
-          .unwrap_or_else(|| panic!("could not find division with id: {id}"));
-        match db_division.name.as_str() {
-          "Metropolitan" => crate::Division::Metropolitan,
-          "Pacific" => crate::Division::Pacific,
-          "Atlantic" => crate::Division::Atlantic,
-          "Central" => crate::Division::Central,
-          _ => panic!("Found division with invalid name: {}", db_division.name),
+// The headless subcommands, for scripts and cron jobs. Each one returns an error instead of
+// printing it, so `main` can exit with a non-zero status.
+mod cli {
+  use crate::*;
+
+  pub const USAGE: &str =
+    "usage: hockey-plots [--db <path>] [--data-dir <dir>] [--source api|<dir>] [command]
+
+commands:
+  gui                                  open the window (the default)
+  sync [--season <id>] [--full]        fetch a season from the source, the NHL api by default.
+                                       only teams with games that aren't final are fetched,
+                                       unless there's --full
+  sync --league [--season <id>] [--from <date>] [--to <date>]
+                                       fetch the league-wide scoreboard for each day that isn't
+                                       final yet, through today by default
+  import [<dir>]                       import schedule json dumps, from the data dir by default
+  standings [--season <id>]            print the standings
+  export csv|json [--season <id>] [--out <path>]
+                                       write the standings to a file, or stdout
+  export csv|json --series [--season <id>] [--out <path>] [series options]
+                                       write each team's plotted points instead
+  render png|svg --out <path> [--season <id>] [image options] [series options]
+                                       draw the regular season plot to an image
+
+image options:
+  --width <pixels>                     1280 by default
+  --height <pixels>                    720 by default
+  --x-axis game|date                   game number by default
+
+series options:
+  --points nhl|iihf|wins|pct           the points system, nhl by default
+  --pace <points per game>|cut|average|none
+                                       what's subtracted from each game, .500 by default
+  --teams <abbrev>,<abbrev>,...        every team by default
+
+seasons are identified like 20232024, and default to the latest one in the db";
+
+  pub fn run(command: &str, args: &[String], config: &Config) -> Result<(), CliError> {
+    match (command, args) {
+      ("sync", args) if args.iter().any(|arg| arg == "--league") => {
+        sync_league(config, season_arg(args)?, args)
+      }
+      ("sync", args) => sync(config, season_arg(args)?, args),
+      ("import", []) => import(config, &config.data_dir),
+      ("import", [dir]) => import(config, Path::new(dir)),
+      ("standings", args) => {
+        let (teams, season, data) = load(config, season_arg(args)?)?;
+        println!("{}", season.label());
+        print!("{}", standings_table(&data.standings, &teams));
+        Ok(())
+      }
+      ("export", [format, args @ ..]) => {
+        let format = match format.as_str() {
+          "csv" => ExportFormat::Csv,
+          "json" => ExportFormat::Json,
+          _ => {
+            return Err(CliError::Usage(format!(
+              "unknown export format: '{format}'"
+            )))
+          }
+        };
+        let (teams, _season, data) = load(config, season_arg(args)?)?;
+        let contents = if args.iter().any(|arg| arg == "--series") {
+          let all_series = series_args(&data, args)?;
+          match format {
+            ExportFormat::Csv => team_series::to_csv(&all_series),
+            ExportFormat::Json => team_series::to_json(&all_series)?,
+          }
+        } else {
+          match format {
+            ExportFormat::Csv => standings_csv(&data.standings, &teams),
+            ExportFormat::Json => standings_json(&data.standings, &teams)?,
+          }
+        };
+        match flag(args, "--out")? {
+          Some(path) => std::fs::write(path, contents)?,
+          None => print!("{contents}"),
         }
+        Ok(())
       }
-
-      crate::Team {
-        db_id: self.id,
-        api_id: self.api_id,
-        color: egui::Color32::from_rgb(self.r as u8, self.g as u8, self.b as u8),
-        abbrev: self.abbrev,
-        division: find_division(self.division_id, db_divisions),
+      ("render", [format, args @ ..]) => {
+        let format = match format.as_str() {
+          "png" => render::ImageFormat::Png,
+          "svg" => render::ImageFormat::Svg,
+          _ => return Err(CliError::Usage(format!("unknown image format: '{format}'"))),
+        };
+        let Some(path) = flag(args, "--out")? else {
+          return Err(CliError::Usage("render needs --out".to_string()));
+        };
+        let x_axis = match flag(args, "--x-axis")? {
+          Some("game") => XAxis::GameNumber,
+          Some("date") => XAxis::Date,
+          Some(x_axis) => return Err(CliError::Usage(format!("unknown x axis: '{x_axis}'"))),
+          None => View::default().x_axis,
+        };
+        let (_teams, season, data) = load(config, season_arg(args)?)?;
+        let (points_system, pace) = metric_args(args)?;
+        let options = render::Options {
+          format,
+          width: size_arg(args, "--width", 1280)?,
+          height: size_arg(args, "--height", 720)?,
+          x_axis,
+          title: format!("{} {}", season.label(), points_system.label()),
+          y_axis_label: pace.y_axis_label(points_system),
+          pace_line: pace != Pace::Zero,
+        };
+        let all_series = series_args(&data, args)?;
+        Ok(render::render(
+          Path::new(path),
+          &data,
+          &all_series,
+          &options,
+        )?)
+      }
+      ("help" | "--help" | "-h", _) => {
+        println!("{USAGE}");
+        Ok(())
       }
+      (command @ ("import" | "export" | "render"), _) => {
+        Err(CliError::Usage(format!("bad arguments for {command}")))
+      }
+      (command, _) => Err(CliError::Usage(format!("unknown command: '{command}'"))),
     }
   }
 
-  #[derive(Copy, Clone, Debug, serde::Deserialize)]
-  pub struct Score {
-    pub id: i32,
-    pub home: i32,
-    pub away: i32,
-    pub last_period_type_id: i32,
+  // The value after `name`, like the path in `--out standings.csv`.
+  fn flag<'a>(args: &'a [String], name: &str) -> Result<Option<&'a str>, CliError> {
+    match args.iter().position(|arg| arg == name) {
+      Some(idx) => match args.get(idx + 1) {
+        Some(value) => Ok(Some(value)),
+        None => Err(CliError::Usage(format!("{name} needs a value"))),
+      },
+      None => Ok(None),
+    }
   }
 
-  #[derive(Clone, Debug, serde::Deserialize)]
-  pub struct Game {
-    pub api_id: i32,
-    pub home_team_id: i32,
-    pub away_team_id: i32,
-    pub score_id: Option<i32>,
+  fn season_arg(args: &[String]) -> Result<Option<i32>, CliError> {
+    match flag(args, "--season")? {
+      Some(season) => match season.parse() {
+        Ok(api_id) => Ok(Some(api_id)),
+        Err(_) => Err(CliError::Usage(format!(
+          "seasons look like 20232024, not '{season}'"
+        ))),
+      },
+      None => Ok(None),
+    }
   }
 
-  impl Game {
-    pub fn db_to_crate(
-      self: Game,
-      game_outcome: Option<(Score, LastPeriodType)>,
-      this_team: &crate::Team,
-    ) -> crate::Game {
-      let points = if let Some((score, last_period_type)) = game_outcome {
-        let (this_team_score, opponent_score) = if self.home_team_id == this_team.db_id {
-          (score.home, score.away)
-        } else {
-          (score.away, score.home)
-        };
+  fn size_arg(args: &[String], name: &str, default: u32) -> Result<u32, CliError> {
+    match flag(args, name)? {
+      Some(pixels) => match pixels.parse() {
+        Ok(pixels) if pixels > 0 => Ok(pixels),
+        _ => Err(CliError::Usage(format!(
+          "{name} should be in pixels, not '{pixels}'"
+        ))),
+      },
+      None => Ok(default),
+    }
+  }
 
-        if this_team_score > opponent_score {
-          Some(2.0)
-        } else {
-          Some(match last_period_type.name.as_str() {
-            "Regulation" => 0.0,
-            "Overtime" | "Shootout" => 1.0,
-            other => panic!("found unknown last_period_type in db: '{other}'"),
-          })
+  // What gets plotted, from `--points` and `--pace`.
+  fn metric_args(args: &[String]) -> Result<(PointsSystem, Pace), CliError> {
+    let points_system = match flag(args, "--points")? {
+      Some(key) => PointsSystem::ALL
+        .into_iter()
+        .find(|points_system| points_system.key() == key)
+        .ok_or(CliError::Usage(format!("unknown points system: '{key}'")))?,
+      None => View::default().points_system,
+    };
+    let pace = match flag(args, "--pace")? {
+      Some("cut") => Pace::PlayoffCutLine,
+      Some("average") => Pace::LeagueAverage,
+      Some("none") => Pace::Zero,
+      Some(points_per_game) => match points_per_game.parse::<f32>() {
+        Ok(points_per_game) => Pace::Fraction(points_per_game / points_system.max_points()),
+        Err(_) => {
+          return Err(CliError::Usage(format!(
+            "unknown pace: '{points_per_game}'"
+          )))
         }
-      } else {
-        None
-      };
-
-      crate::Game { points }
-    }
+      },
+      None => View::default().pace,
+    };
+    Ok((points_system, pace))
   }
-}
 
-mod json {
-  use serde::Deserialize;
+  // Builds the series that the plot would show for `--points`, `--pace`, and `--teams`.
+  fn series_args(
+    data: &AppData,
+    args: &[String],
+  ) -> Result<Vec<team_series::TeamSeries>, CliError> {
+    let (points_system, pace) = metric_args(args)?;
+    let mut teams: Vec<&Team> = match flag(args, "--teams")? {
+      Some(abbrevs) => abbrevs
+        .split(',')
+        .map(|abbrev| {
+          data
+            .teams
+            .iter()
+            .find(|team| team.abbrev.eq_ignore_ascii_case(abbrev.trim()))
+            .ok_or(CliError::Usage(format!("unknown team: '{abbrev}'")))
+        })
+        .collect::<Result<_, _>>()?,
+      None => data.teams.iter().collect(),
+    };
+    teams.sort_by(|a, b| a.abbrev.cmp(&b.abbrev));
 
-  #[derive(Debug, Deserialize, Copy, Clone)]
-  pub struct GameTeam {
-    pub id: i32,
-    pub score: Option<i32>,
+    Ok(team_series::build(data, &teams, points_system, pace))
   }
 
-  #[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq, Hash)]
-  #[allow(clippy::upper_case_acronyms)]
-  pub enum PeriodType {
-    SO,
-    REG,
-    OT,
+  fn season(db: &DB, season_api_id: Option<i32>) -> Result<Season, CliError> {
+    let seasons = db.get_seasons()?;
+    match season_api_id {
+      Some(api_id) => seasons
+        .into_iter()
+        .find(|season| season.api_id == api_id)
+        .ok_or(CliError::Usage(format!("season {api_id} isn't in the db"))),
+      None => seasons.last().copied().ok_or(CliError::Usage(
+        "there are no seasons in the db".to_string(),
+      )),
+    }
   }
 
-  #[derive(Debug, Deserialize, Copy, Clone)]
-  #[serde(rename_all = "camelCase")]
-  pub struct GameOutcome {
-    pub last_period_type: PeriodType,
+  fn load(
+    config: &Config,
+    season_api_id: Option<i32>,
+  ) -> Result<(Vec<Team>, Season, AppData), CliError> {
+    let db = DB::initialize(&config.db_path)?;
+    let teams = db.get_teams()?;
+    let season = season(&db, season_api_id)?;
+    let data = db.app_data(&teams, &season)?;
+    Ok((teams, season, data))
   }
 
-  #[derive(Debug, Deserialize, Copy, Clone)]
-  #[serde(rename_all = "camelCase")]
-  pub struct Game {
-    pub id: i32,
-    pub home_team: GameTeam,
-    pub away_team: GameTeam,
-    pub game_type: u8,
-    pub game_outcome: Option<GameOutcome>,
-  }
+  // Only fetches teams that have games that aren't final yet, unless there's `--full`.
+  fn sync(config: &Config, season_api_id: Option<i32>, args: &[String]) -> Result<(), CliError> {
+    let db = DB::initialize(&config.db_path)?;
+    let teams = db.get_teams()?;
+    // Syncing is how new seasons get into the db, which `insert_schedules` takes care of.
+    let season_api_id = match season_api_id {
+      Some(api_id) => api_id,
+      None => season(&db, None)?.api_id,
+    };
+    let mut sync_teams = teams.clone();
+    if !args.iter().any(|arg| arg == "--full") {
+      let team_api_ids = db.unfinished_teams(&teams, season_api_id)?;
+      sync_teams.retain(|team| team_api_ids.contains(&team.api_id));
+      if sync_teams.is_empty() {
+        println!("season {season_api_id} is over, nothing to sync (--full fetches it anyway)");
+        return Ok(());
+      }
+    }
 
-  pub enum GameType {
-    Preseason,
-    RegularSeason,
-    Playoffs,
+    let source = config.schedule_source();
+    let runtime = tokio::runtime::Runtime::new()?;
+    let api_response = runtime.block_on(source.load_season(&sync_teams, season_api_id))?;
+    let summary = db.insert_schedules(api_response, &teams)?;
+    println!(
+      "synced season {season_api_id} from {}, {} teams: {summary}",
+      source.describe(),
+      sync_teams.len()
+    );
+    print_changes(&summary);
+    Ok(())
   }
 
-  impl Game {
-    pub fn get_type(self) -> GameType {
-      use crate::json::GameType::*;
+  fn print_changes(summary: &InsertSummary) {
+    for change in &summary.changes {
+      println!("  {change}");
+    }
+  }
 
-      match self.game_type {
-        1 => Preseason,
-        2 => RegularSeason,
-        3 => Playoffs,
-        _ => panic!("unknown gameType: {}", self.game_type),
+  // Fetches the league-wide scoreboard for each day from `--from` to `--to` that isn't final yet.
+  // The range defaults to the season's first game in the db up to today, see `DB::league_days`.
+  fn sync_league(
+    config: &Config,
+    season_api_id: Option<i32>,
+    args: &[String],
+  ) -> Result<(), CliError> {
+    fn date_arg(args: &[String], name: &str) -> Result<Option<NaiveDate>, CliError> {
+      match flag(args, name)? {
+        Some(date) => match date.parse() {
+          Ok(date) => Ok(Some(date)),
+          Err(_) => Err(CliError::Usage(format!(
+            "{name} should look like 2023-10-10, not '{date}'"
+          ))),
+        },
+        None => Ok(None),
       }
     }
 
-    // TODO Consolidate with db::db_to_crate.
-    pub fn api_to_crate(self, this_team: &crate::Team) -> crate::Game {
-      let points = if let Some(outcome) = self.game_outcome {
-        let (this_team_score, opponent_score) = if self.home_team.id == this_team.api_id {
-          (self.home_team.score, self.away_team.score)
-        } else {
-          (self.away_team.score, self.home_team.score)
-        };
+    if config.source != "api" {
+      return Err(CliError::Usage(
+        "--league only works with the NHL api source".to_string(),
+      ));
+    }
+    let db = DB::initialize(&config.db_path)?;
+    let teams = db.get_teams()?;
+    let season_api_id = match season_api_id {
+      Some(api_id) => api_id,
+      None => season(&db, None)?.api_id,
+    };
 
-        if this_team_score.is_some() {
-          if this_team_score > opponent_score {
-            Some(2.0)
-          } else {
-            match outcome.last_period_type {
-              PeriodType::REG => Some(0.0),
-              PeriodType::OT | PeriodType::SO => Some(1.0),
-            }
-          }
-        } else {
-          None
-        }
-      } else {
-        None
-      };
+    let (from, to, dates) = db.league_days(
+      season_api_id,
+      date_arg(args, "--from")?,
+      date_arg(args, "--to")?,
+      chrono::Utc::now().date_naive(),
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let scoreboards = runtime.block_on(config.nhl_api().load_days(&dates))?;
+    let summary = db.insert_scoreboards(season_api_id, scoreboards, &teams)?;
+    println!(
+      "synced season {season_api_id} from the NHL api, {} days from {from} to {to}: {summary}",
+      dates.len()
+    );
+    print_changes(&summary);
+    Ok(())
+  }
 
-      crate::Game { points }
+  fn import(config: &Config, dir: &Path) -> Result<(), CliError> {
+    let db = DB::initialize(&config.db_path)?;
+    let teams = db.get_teams()?;
+    let loaded = json::files::load_dir(dir)?;
+    for (path, reason) in &loaded.skipped {
+      eprintln!("skipped {}: {reason}", path.display());
+    }
+    for api_response in loaded.api_responses {
+      let season = api_response.season;
+      let summary = db.insert_schedules(api_response, &teams)?;
+      println!("imported season {season}: {summary}");
     }
+    Ok(())
   }
 
-  #[derive(Clone, Debug, Deserialize)]
-  pub struct TeamSchedule {
-    pub games: Vec<Game>,
+  fn standings_rows(standings: &[standings::Standing], teams: &[Team]) -> Vec<Vec<String>> {
+    let header = standings::Column::ALL
+      .iter()
+      .map(|column| column.label().to_string())
+      .collect();
+    let rows = standings.iter().enumerate().map(|(rank, standing)| {
+      standings::Column::ALL
+        .iter()
+        .map(|column| match column {
+          standings::Column::Rank => format!("{}", rank + 1),
+          standings::Column::Team => abbrev(teams, standing.team_api_id),
+          column => column.text(standing),
+        })
+        .collect()
+    });
+    std::iter::once(header).chain(rows).collect()
   }
 
-  #[derive(Clone, Debug, Deserialize)]
-  pub struct ApiResponse {
-    // Key is `team.api_id`.
-    pub schedules: std::collections::HashMap<i32, TeamSchedule>,
+  fn abbrev(teams: &[Team], team_api_id: i32) -> String {
+    teams
+      .iter()
+      .find(|team| team.api_id == team_api_id)
+      .map(|team| team.abbrev.clone())
+      .unwrap_or_default()
   }
 
-  pub mod api {
-    use crate::*;
-    pub async fn load_games(teams: Vec<Team>) -> Result<json::ApiResponse, request::Error> {
-      let mut api_response = json::ApiResponse {
-        schedules: std::collections::HashMap::new(),
-      };
-      for team in teams {
-        let json_contents = request::get(format!(
-          "https://api-web.nhle.com/v1/club-schedule-season/{}/20232024",
-          team.abbrev
-        ))
-        .await?
-        .text()
-        .await?;
-        let schedule: json::TeamSchedule = serde_json::from_str(&json_contents).expect("no");
-        api_response.schedules.insert(team.api_id, schedule);
-      }
-      Ok(api_response)
+  fn standings_table(standings: &[standings::Standing], teams: &[Team]) -> String {
+    let rows = standings_rows(standings, teams);
+    let widths: Vec<usize> = (0..standings::Column::ALL.len())
+      .map(|idx| rows.iter().map(|row| row[idx].len()).max().unwrap_or(0))
+      .collect();
+    let mut table = String::new();
+    for row in rows {
+      let cells: Vec<String> = row
+        .iter()
+        .zip(&widths)
+        .map(|(cell, width)| format!("{cell:>width$}"))
+        .collect();
+      table.push_str(&cells.join("  "));
+      table.push('\n');
     }
+    table
   }
-}
-
-#[derive(Debug)]
-struct DB {
-  conn: rusqlite::Connection,
-}
 
-use std::path::Path;
-
-impl DB {
-  fn all<T: for<'a> serde::Deserialize<'a>>(&self, table_name: &str) -> Vec<T> {
-    // TODO Not sure why this can't use rusqlite's `?1` params - getting SqlInputError with `code: Unknown`.
-    let mut statement = self
-      .conn
-      .prepare(&format!("SELECT * FROM {};", table_name))
-      .unwrap();
-    let res = serde_rusqlite::from_rows::<T>(
-      statement
-        .query([])
-        .unwrap_or_else(|e| panic!("error from DB::all for table {table_name}: {:?}", e)),
-    );
-    res.flatten().collect()
+  fn standings_csv(standings: &[standings::Standing], teams: &[Team]) -> String {
+    standings_rows(standings, teams)
+      .iter()
+      .map(|row| row.join(",") + "\n")
+      .collect()
   }
 
-  fn all_scores(&self) -> Vec<db::Score> {
-    self.all::<db::Score>("scores")
-  }
+  fn standings_json(standings: &[standings::Standing], teams: &[Team]) -> Result<String, CliError> {
+    #[derive(serde::Serialize)]
+    struct Row<'a> {
+      rank: usize,
+      team: String,
+      #[serde(flatten)]
+      standing: &'a standings::Standing,
+      points_percentage: f32,
+      goal_differential: i32,
+    }
 
-  fn all_divisions(&self) -> Vec<db::Division> {
-    self.all::<db::Division>("divisions")
+    let rows: Vec<Row> = standings
+      .iter()
+      .enumerate()
+      .map(|(rank, standing)| Row {
+        rank: rank + 1,
+        team: abbrev(teams, standing.team_api_id),
+        standing,
+        points_percentage: standing.points_percentage(),
+        goal_differential: standing.goal_differential(),
+      })
+      .collect();
+    Ok(serde_json::to_string_pretty(&rows)? + "\n")
   }
+}
 
-  fn all_last_period_types(&self) -> Vec<db::LastPeriodType> {
-    self.all::<db::LastPeriodType>("last_period_types")
-  }
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct ShowDivision {
+  central: bool,
+  pacific: bool,
+  atlantic: bool,
+  metro: bool,
+  // Teams that are unchecked in the team list, by api_id.
+  hidden: std::collections::BTreeSet<i32>,
+  // Teams that are highlighted on the plot, by api_id.
+  selected: std::collections::BTreeSet<i32>,
+  // Hides every team that isn't selected, as long as at least one is.
+  only_selected: bool,
+}
 
-  fn period_type_ids(&self) -> HashMap<json::PeriodType, i32> {
-    fn get_period_type_id(db: &DB, name: &str) -> i32 {
-      db.conn
-        .query_row(
-          "SELECT id FROM last_period_types WHERE name = ?1",
-          [name],
-          |r| r.get(0),
-        )
-        .unwrap()
+impl ShowDivision {
+  fn division(&self, division: Division) -> bool {
+    match division {
+      Division::Metropolitan => self.metro,
+      Division::Central => self.central,
+      Division::Pacific => self.pacific,
+      Division::Atlantic => self.atlantic,
     }
-    let mut period_type_ids: HashMap<json::PeriodType, i32> = HashMap::new();
-    period_type_ids.insert(
-      json::PeriodType::REG,
-      get_period_type_id(self, "Regulation"),
-    );
-    period_type_ids.insert(json::PeriodType::SO, get_period_type_id(self, "Shootout"));
-    period_type_ids.insert(json::PeriodType::OT, get_period_type_id(self, "Overtime"));
-    period_type_ids
   }
 
-  fn all_teams(&self) -> Vec<db::Team> {
-    self.all::<db::Team>("teams")
+  fn team(&self, team: &Team) -> bool {
+    self.division(team.division) && !self.hidden.contains(&team.api_id)
   }
 
-  fn all_db_games(&self, teams: &[Team]) -> DbGamesByTeam {
-    let mut games: DbGamesByTeam = HashMap::new();
-
-    for team in teams {
-      let mut db_games = vec![];
-      let mut statement = self
-        .conn
-        .prepare("SELECT * FROM games WHERE games.home_team_id = ?1 OR games.away_team_id = ?2;")
-        .unwrap();
-      let res = serde_rusqlite::from_rows::<db::Game>(
-        statement
-          .query([team.db_id, team.db_id])
-          .unwrap_or_else(|e| panic!("error while SELECTing games: {:?}", e)),
-      );
-      for db_game in res.flatten() {
-        db_games.push(db_game)
-      }
-
-      games.insert(team.api_id, db_games);
+  fn division_mut(&mut self, division: Division) -> &mut bool {
+    match division {
+      Division::Metropolitan => &mut self.metro,
+      Division::Central => &mut self.central,
+      Division::Pacific => &mut self.pacific,
+      Division::Atlantic => &mut self.atlantic,
     }
-    games
   }
 
-  fn all_games(&self, teams: &[Team]) -> GamesByTeam {
-    let games_by_team: DbGamesByTeam = self.all_db_games(teams);
-    let scores = self.all_scores();
-    let last_period_types = self.all_last_period_types();
-    fn find_score(
-      score_id: i32,
-      scores: &[db::Score],
-      last_period_types: &[db::LastPeriodType],
-    ) -> Option<(db::Score, db::LastPeriodType)> {
-      if let Some(score) = scores.iter().find(|score| score.id == score_id) {
-        let lpt: db::LastPeriodType = last_period_types
-          .iter()
-          .find(|period_type| period_type.id == score.last_period_type_id)
-          .unwrap()
-          .clone();
-        Some((*score, lpt))
-      } else {
-        None
+  // Shows or hides each of `teams` that's in `group`.
+  fn set_teams(&mut self, teams: &[Team], group: impl Fn(&Team) -> bool, show: bool) {
+    for team in teams.iter().filter(|team| group(team)) {
+      if !show {
+        self.hidden.insert(team.api_id);
+        continue;
       }
-    }
-    let mut all_games = HashMap::new();
-    for team in teams {
-      let team_db_games = games_by_team.get(&team.api_id).unwrap();
-      let mut team_games = vec![];
-      for db_game in team_db_games {
-        let game_outcome = if let Some(score_id) = db_game.score_id {
-          find_score(score_id, &scores, &last_period_types)
-        } else {
-          None
-        };
-        let team_game: Game = db_game.clone().db_to_crate(game_outcome, team);
-        team_games.push(team_game)
-      }
-      all_games.insert(team.api_id, team_games);
-    }
-    all_games
-  }
-
-  fn insert_schedules(
-    &self,
-    schedules: HashMap<i32, json::TeamSchedule>,
-    teams: &[Team],
-  ) -> Result<u32, &str> {
-    let mut insert_game_statement = self
-      .conn
-      .prepare("INSERT INTO games (api_id, home_team_id, away_team_id, score_id) VALUES (:api_id, :home_team_id, :away_team_id, :score_id);")
-      .unwrap();
-    let mut update_game_statement = self
-      .conn
-      .prepare("UPDATE games SET score_id = :score_id WHERE api_id = :api_id;")
-      .unwrap();
-    let mut score_statement = self
-      .conn
-      .prepare("INSERT INTO scores (home, away, last_period_type_id) VALUES (:home, :away, :last_period_type_id);")
-      .unwrap();
-
-    let period_type_ids: HashMap<json::PeriodType, i32> = self.period_type_ids();
-
-    let all_db_games = self.all_db_games(teams);
-
-    let mut finished_game_ids: HashSet<i32> = HashSet::new();
-    let api_ids: Vec<u8> = self
-      .conn
-      .query_row(
-        "SELECT api_id FROM games WHERE games.score_id IS NOT NULL",
-        [],
-        |r| r.get(0),
-      )
-      .unwrap();
-
-    for api_id in api_ids {
-      finished_game_ids.insert(api_id as i32);
-    }
-
-    use crate::json::GameType::*;
-    for (_team_api_id, schedule) in schedules {
-      for json_game in schedule.games {
-        let mut score_id = None;
-        if let Some(outcome) = json_game.game_outcome {
-          match json_game.get_type() {
-            Preseason | Playoffs => (),
-            RegularSeason => {
-              if finished_game_ids.get(&json_game.id).is_none() {
-                finished_game_ids.insert(json_game.id);
-                fn get_score(team: json::GameTeam, team_str: &str) -> i32 {
-                  team.score.unwrap_or_else(|| {
-                    panic!("json_game with an outcome should have a score for the {team_str} team")
-                  })
-                }
-                score_id = Some(
-                  score_statement
-                    .insert(rusqlite::named_params! {
-                      ":home": get_score(json_game.home_team, "home"),
-                      ":away": get_score(json_game.away_team, "away"),
-                      ":last_period_type_id": *period_type_ids.get(&outcome.last_period_type).unwrap(),
-                    })
-                    .unwrap(),
-                );
-              }
-            }
-          }
-        } else {
-          println!("got no gameOutcome for {}", json_game.id);
-        }
-        fn get_db_id(game_team: json::GameTeam, teams: &[Team]) -> i32 {
-          teams
-            .iter()
-            .find(|team| team.api_id == game_team.id)
-            .unwrap()
-            .db_id
-        }
-        match json_game.get_type() {
-          Preseason | Playoffs => (),
-          RegularSeason => {
-            if let Some(home_team_db_games) = all_db_games.get(&json_game.home_team.id) {
-              if let Some(db_game) = home_team_db_games
-                .iter()
-                .find(|db_game| db_game.api_id == json_game.id)
-              {
-                if score_id.is_some() && db_game.score_id.is_none() {
-                  match update_game_statement
-                    .execute(rusqlite::named_params! {":score_id": score_id})
-                  {
-                    Ok(_) => (),
-                    Err(e) => {
-                      println!(
-                        "error on insert_game_statement.execute: {:?}\n with params:\n{}, {}, {}, {:?}",
-                        e, json_game.id, json_game.home_team.id, json_game.away_team.id, score_id,
-                      )
-                    }
-                  }
-                }
-              } else {
-                match insert_game_statement.execute(rusqlite::named_params! {
-                  ":api_id": json_game.id,
-                  ":home_team_id": get_db_id(json_game.home_team, teams),
-                  ":away_team_id": get_db_id(json_game.away_team, teams),
-                  ":score_id": score_id,
-                }) {
-                  Ok(_) => (),
-                  Err(e) => {
-                    println!(
-                      "error on insert_game_statement.execute: {:?}\n with params:\n{}, {}, {}, {:?}",
-                      e, json_game.id, json_game.home_team.id, json_game.away_team.id, score_id,
-                    )
-                  }
-                }
-              }
-            } else {
-              panic!(
-                "could not find team with api_id {} in all_games",
-                json_game.home_team.id
-              )
-            }
-          }
+      if !self.division(team.division) {
+        // Turning the division back on shouldn't bring back the rest of it.
+        for other in teams.iter().filter(|other| other.division == team.division) {
+          self.hidden.insert(other.api_id);
         }
+        *self.division_mut(team.division) = true;
       }
+      self.hidden.remove(&team.api_id);
     }
-
-    todo!()
   }
 
-  fn execute_file(&self, path: &str) {
-    use std::io::Read;
-
-    let mut f = std::fs::File::open(path)
-      .unwrap_or_else(|_| panic!("could not find sql file at path: {path}"));
-    let mut buffer = String::new();
-    f.read_to_string(&mut buffer).unwrap().to_string();
-
-    match self.conn.execute_batch(&buffer) {
-      Ok(_) => println!("initialized db"),
-      Err(_) => println!("db initialization skipped"),
-    };
+  // Whether the team is drawn at all, since `only_selected` hides teams that are still in the legend.
+  fn visible(&self, team: &Team) -> bool {
+    self.team(team) && !(self.only_selected && self.is_dimmed(team.api_id))
   }
 
-  pub fn initialize(db_path: &Path) -> Self {
-    let conn = rusqlite::Connection::open(db_path)
-      .unwrap_or_else(|_| panic!("could not find db file at path: {:?}", db_path));
-    let db = Self { conn };
-    db.execute_file("../data/init.sql");
-    db
+  fn is_dimmed(&self, team_api_id: i32) -> bool {
+    !self.selected.is_empty() && !self.selected.contains(&team_api_id)
   }
 
-  fn get_teams(&self) -> Vec<Team> {
-    let db_divisions = self.all_divisions();
-    let db_teams: Vec<db::Team> = self.all_teams();
-    db_teams
-      .iter()
-      .map(|db_team| db_team.clone().db_to_crate(&db_divisions))
-      .collect()
+  // A plain click selects only that team, and ctrl-click adds or removes it from the selection.
+  fn click(&mut self, team_api_id: i32, modifiers: egui::Modifiers) {
+    if modifiers.command {
+      if !self.selected.remove(&team_api_id) {
+        self.selected.insert(team_api_id);
+      }
+    } else if self.selected.len() == 1 && self.selected.contains(&team_api_id) {
+      self.selected.clear();
+    } else {
+      self.selected = [team_api_id].into();
+    }
   }
 }
 
-#[derive(Copy, Clone, Debug)]
-struct ShowDivision {
-  central: bool,
-  pacific: bool,
-  atlantic: bool,
-  metro: bool,
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum ExportFormat {
+  Csv,
+  Json,
 }
 
-// Key is the Team's api_id.
-type GamesByTeam = HashMap<i32, Vec<Game>>;
-type DbGamesByTeam = HashMap<i32, Vec<db::Game>>;
-
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-enum Division {
-  Central,
-  Pacific,
-  Atlantic,
-  Metropolitan,
-}
-
-#[derive(Clone, Debug)]
-struct Team {
-  db_id: i32,
-  api_id: i32,
-  color: Color32,
-  abbrev: String,
-  division: Division,
+impl ExportFormat {
+  fn extension(self) -> &'static str {
+    match self {
+      ExportFormat::Csv => "csv",
+      ExportFormat::Json => "json",
+    }
+  }
 }
 
-#[derive(Copy, Clone, Debug)]
-struct Game {
-  points: Option<f32>,
+#[derive(Copy, Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+enum Tab {
+  RegularSeason,
+  Playoffs,
+  Standings,
 }
 
-#[derive(Clone, Debug)]
-struct AppData {
-  games: GamesByTeam,
-  teams: Vec<Team>,
+#[derive(Copy, Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+enum PlayoffsView {
+  Wins,
+  Bracket,
 }
 
 #[derive(Debug)]
 struct App {
-  db: DB,
-  tx: std::sync::mpsc::Sender<json::ApiResponse>,
-  rx: std::sync::mpsc::Receiver<json::ApiResponse>,
+  // None when the db couldn't be opened, which is in the error banner.
+  db: Option<DB>,
+  tx: std::sync::mpsc::Sender<Result<json::ApiResponse, Error>>,
+  rx: std::sync::mpsc::Receiver<Result<json::ApiResponse, Error>>,
   data: AppData,
+  // Shown in a banner until it's dismissed.
+  error: Option<Error>,
+  seasons: Vec<Season>,
+  // None until there's a season in the db.
+  season: Option<Season>,
+  // A season to add to the picker, like 20242025, which "update" then fetches.
+  new_season: String,
+  view: View,
+  // Set by "reset view" so the plot forgets its zoom on the next frame.
+  reset_plot: bool,
+  // Filters the team list by abbreviation.
+  team_search: String,
+  import_dir: String,
+  // Where "update" gets schedules from.
+  source: std::sync::Arc<dyn source::ScheduleSource>,
+  // The extension is replaced to match the export format.
+  export_path: String,
+  // Where the last export was written.
+  exported: Option<PathBuf>,
+  // What the last update or import did, one line each.
+  status: Vec<String>,
+}
+
+// Everything about how the data is shown, which is saved between launches.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct View {
   show: ShowDivision,
+  tab: Tab,
+  playoffs_view: PlayoffsView,
+  points_system: PointsSystem,
+  pace: Pace,
+  // Colors lines by each team's current playoff seed instead of by team.
+  playoff_picture: bool,
+  x_axis: XAxis,
+  standings_sort: standings::Column,
+  standings_descending: bool,
 }
 
-impl Default for App {
+impl Default for View {
   fn default() -> Self {
+    Self {
+      show: ShowDivision {
+        central: true,
+        pacific: true,
+        atlantic: true,
+        metro: true,
+        hidden: Default::default(),
+        selected: Default::default(),
+        only_selected: false,
+      },
+      tab: Tab::RegularSeason,
+      playoffs_view: PlayoffsView::Wins,
+      points_system: PointsSystem::Nhl,
+      pace: Pace::Fraction(0.5),
+      playoff_picture: false,
+      x_axis: XAxis::GameNumber,
+      standings_sort: standings::Column::Rank,
+      standings_descending: false,
+    }
+  }
+}
+
+impl App {
+  fn new(cc: &eframe::CreationContext, config: Config) -> Self {
     let (tx, rx) = std::sync::mpsc::channel();
 
-    let path = std::path::Path::new("../data/hockeyplots.db");
-    let db = DB::initialize(path);
+    // Anything that fails to load is left empty, and the window shows the error instead.
+    fn or_empty<T: Default>(result: Result<T, Error>, error: &mut Option<Error>) -> T {
+      result.unwrap_or_else(|e| {
+        error.get_or_insert(e);
+        T::default()
+      })
+    }
+
+    let mut error = None;
+    let db = match DB::initialize(&config.db_path) {
+      Ok(db) => Some(db),
+      Err(e) => {
+        error = Some(e);
+        None
+      }
+    };
+    let (teams, seasons) = match &db {
+      Some(db) => (
+        or_empty(db.get_teams(), &mut error),
+        or_empty(db.get_seasons(), &mut error),
+      ),
+      None => (vec![], vec![]),
+    };
+    let season = seasons.last().copied();
+    let data = match (&db, season) {
+      (Some(db), Some(season)) => db.app_data(&teams, &season).unwrap_or_else(|e| {
+        error.get_or_insert(e);
+        AppData::empty(teams.clone())
+      }),
+      _ => AppData::empty(teams),
+    };
 
-    let teams = db.get_teams();
-    let games = db.all_games(&teams);
+    let view = cc
+      .storage
+      .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+      .unwrap_or_default();
 
     Self {
       db,
       tx,
       rx,
-      data: AppData { games, teams },
-      show: ShowDivision {
-        central: true,
-        pacific: true,
-        atlantic: true,
-        metro: true,
-      },
+      data,
+      error,
+      seasons,
+      season,
+      new_season: String::new(),
+      view,
+      reset_plot: false,
+      team_search: String::new(),
+      import_dir: config.data_dir.display().to_string(),
+      source: config.schedule_source(),
+      export_path: config.data_dir.join("series.csv").display().to_string(),
+      exported: None,
+      status: vec![],
+    }
+  }
+}
+
+// One team's line on the regular season plot.
+struct TeamLine {
+  team_api_id: i32,
+  name: String,
+  color: Color32,
+  points: Vec<PlotPoint>,
+  // What to show when hovering each game, keyed by its x value.
+  hover_text: Vec<(f64, String)>,
+}
+
+impl TeamLine {
+  fn id(&self) -> egui::Id {
+    egui::Id::new(("team line", self.team_api_id))
+  }
+}
+
+impl App {
+  // Writes the series for every team that's drawn on the plot.
+  fn export_series(&self, format: ExportFormat) -> Result<PathBuf, Error> {
+    let mut teams: Vec<&Team> = self
+      .data
+      .teams
+      .iter()
+      .filter(|team| self.view.show.visible(team))
+      .collect();
+    teams.sort_by(|a, b| a.abbrev.cmp(&b.abbrev));
+    let all_series =
+      team_series::build(&self.data, &teams, self.view.points_system, self.view.pace);
+
+    let path = Path::new(&self.export_path).with_extension(format.extension());
+    let contents = match format {
+      ExportFormat::Csv => team_series::to_csv(&all_series),
+      ExportFormat::Json => team_series::to_json(&all_series)?,
+    };
+    std::fs::write(&path, contents)?;
+    Ok(path)
+  }
+
+  // Lines for every shown team, sorted by name for the legend.
+  fn team_lines(&self, origin: f64, seeds: &HashMap<i32, standings::Seed>) -> Vec<TeamLine> {
+    fn seed_color(seed: standings::Seed) -> Color32 {
+      match seed {
+        standings::Seed::Division(1) => Color32::DARK_GREEN,
+        standings::Seed::Division(_) => Color32::GREEN,
+        standings::Seed::WildCard(_) => Color32::from_rgb(255, 165, 0),
+        standings::Seed::Out => Color32::DARK_GRAY,
+      }
+    }
+
+    let teams: Vec<&Team> = self
+      .data
+      .teams
+      .iter()
+      .filter(|team| self.view.show.team(team) && self.data.games.contains_key(&team.api_id))
+      .collect();
+    let all_series =
+      team_series::build(&self.data, &teams, self.view.points_system, self.view.pace);
+
+    let mut lines = vec![];
+    for (team, series) in teams.into_iter().zip(all_series) {
+      // Prefixing the seed groups the legend by seed, since it's sorted by name.
+      let (name, color) = match seeds.get(&team.api_id) {
+        Some(seed) if self.view.playoff_picture => (
+          format!("{} {}", seed.label(), team.abbrev),
+          seed_color(*seed),
+        ),
+        _ => (team.abbrev.clone(), team.color),
+      };
+
+      let mut games: Vec<PlotPoint> = vec![PlotPoint { x: origin, y: 0.0 }];
+      let mut hover_text = vec![];
+      for point in &series.points {
+        let x = self.view.x_axis.point_x(point);
+        games.push(PlotPoint {
+          x,
+          y: point.pace_delta as f64,
+        });
+        hover_text.push((x, point.game.description(&self.data.teams)));
+      }
+
+      lines.push(TeamLine {
+        team_api_id: series.team_api_id,
+        name,
+        color,
+        points: games,
+        hover_text,
+      });
     }
+    lines.sort_by(|a, b| a.name.cmp(&b.name));
+    lines
   }
 }
 
+// Seasons are named by both of their years, like 20242025.
+fn parse_season(text: &str) -> Option<i32> {
+  let api_id: i32 = text.trim().parse().ok()?;
+  (api_id / 10000 + 1 == api_id % 10000).then_some(api_id)
+}
+
+// Gridlines on the first of every month, and thinner ones every Monday. Zoomed out, the Mondays are
+// dropped, and then the months, for the first of every year or of every ten, hundred... years.
+fn date_grid(input: egui_plot::GridInput) -> Vec<egui_plot::GridMark> {
+  let (Some(start), Some(end)) = (XAxis::date(input.bounds.0), XAxis::date(input.bounds.1)) else {
+    return vec![];
+  };
+  let mark = |date: NaiveDate, step_size: f64| egui_plot::GridMark {
+    value: date.num_days_from_ce() as f64,
+    step_size,
+  };
+
+  let months: u32 = if input.base_step_size <= 30.0 {
+    1
+  } else {
+    let mut months = 12;
+    while months as f64 * 30.0 < input.base_step_size {
+      months *= 10;
+    }
+    months
+  };
+  let first = if months == 1 {
+    start.with_day(1)
+  } else {
+    let years = (months / 12) as i32;
+    NaiveDate::from_ymd_opt(start.year() - start.year().rem_euclid(years), 1, 1)
+  };
+  let mut marks: Vec<egui_plot::GridMark> = std::iter::successors(first, |date| {
+    date.checked_add_months(chrono::Months::new(months))
+  })
+  .skip_while(|date| *date < start)
+  .take_while(|date| *date <= end)
+  .map(|date| mark(date, months as f64 * 30.0))
+  .collect();
+
+  if input.base_step_size <= 7.0 {
+    let monday =
+      start + chrono::Days::new(u64::from(7 - start.weekday().num_days_from_monday()) % 7);
+    marks.extend(
+      std::iter::successors(Some(monday), |date| {
+        date.checked_add_days(chrono::Days::new(7))
+      })
+      .take_while(|date| *date <= end)
+      // The month's gridline is already there.
+      .filter(|date| date.day() != 1)
+      .map(|date| mark(date, 7.0)),
+    );
+  }
+  marks
+}
+
 fn _txt(s: &str) -> egui::widget_text::RichText {
   RichText::new(s).size(24.0)
 }
 
 impl eframe::App for App {
+  fn save(&mut self, storage: &mut dyn eframe::Storage) {
+    eframe::set_value(storage, eframe::APP_KEY, &self.view);
+  }
+
   fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-    fn make_app_data(api_response: &json::ApiResponse, teams: &[Team]) -> AppData {
-      let mut games: GamesByTeam = HashMap::new();
-      for (team_api_id, schedule) in &api_response.schedules {
-        let team = teams
-          .iter()
-          .find(|team| team.api_id == *team_api_id)
-          .unwrap();
-        let mut team_games = vec![];
-
-        for game in &schedule.games {
-          use crate::json::GameType::*;
-          match game.get_type() {
-            Preseason | Playoffs => (),
-            RegularSeason => team_games.push(game.api_to_crate(team)),
+    fn find_team(teams: &[Team], api_id: i32) -> Option<&Team> {
+      teams.iter().find(|team| team.api_id == api_id)
+    }
+
+    fn round_name(round: i32) -> String {
+      match round {
+        1 => "First Round".to_string(),
+        2 => "Second Round".to_string(),
+        3 => "Conference Finals".to_string(),
+        4 => "Stanley Cup Final".to_string(),
+        _ => format!("Round {round}"),
+      }
+    }
+
+    fn show_series_wins(ui: &mut egui::Ui, data: &AppData) {
+      egui::ScrollArea::vertical().show(ui, |sui| {
+        for round in 1..=4 {
+          let round_series: Vec<&Series> = data
+            .series
+            .iter()
+            .filter(|series| series.round == round)
+            .collect();
+          if round_series.is_empty() {
+            continue;
           }
+          sui.heading(round_name(round));
+          sui.horizontal_wrapped(|hui| {
+            for series in round_series {
+              egui_plot::Plot::new(format!("series-{}", series.letter))
+                .width(280.0)
+                .height(200.0)
+                .legend(Legend::default())
+                .include_x(0.0)
+                .include_x(series.needed_to_win as f64 * 2.0 - 1.0)
+                .include_y(0.0)
+                .include_y(series.needed_to_win as f64)
+                .allow_drag(false)
+                .allow_scroll(false)
+                .allow_zoom(false)
+                .show(hui, |plot_ui| {
+                  for team_api_id in [series.top_seed(), series.bottom_seed()]
+                    .into_iter()
+                    .flatten()
+                  {
+                    let Some(team) = find_team(&data.teams, team_api_id) else {
+                      continue;
+                    };
+                    let mut wins = vec![PlotPoint { x: 0.0, y: 0.0 }];
+                    let mut wins_so_far = 0;
+                    for game in &series.games {
+                      if let Some(winner) = game.winner {
+                        if winner == team_api_id {
+                          wins_so_far += 1;
+                        }
+                        wins.push(PlotPoint {
+                          x: game.game_number as f64,
+                          y: wins_so_far as f64,
+                        });
+                      }
+                    }
+                    plot_ui.line(
+                      Line::new(PlotPoints::Owned(wins))
+                        .name(team.abbrev.clone())
+                        .color(team.color),
+                    );
+                  }
+                });
+            }
+          });
         }
-        games.insert(team.api_id, team_games);
-      }
+      });
+    }
+
+    fn show_bracket(ui: &mut egui::Ui, data: &AppData) {
+      ui.columns(4, |columns| {
+        for (idx, cui) in columns.iter_mut().enumerate() {
+          let round = 1 + idx as i32;
+          cui.heading(round_name(round));
+          for series in data.series.iter().filter(|series| series.round == round) {
+            cui.group(|gui| {
+              for team_api_id in [series.top_seed(), series.bottom_seed()]
+                .into_iter()
+                .flatten()
+              {
+                let Some(team) = find_team(&data.teams, team_api_id) else {
+                  continue;
+                };
+                let mut text =
+                  RichText::new(format!("{}  {}", team.abbrev, series.wins(team_api_id)))
+                    .size(18.0)
+                    .color(team.color);
+                if series.winner() == Some(team_api_id) {
+                  text = text.strong();
+                }
+                gui.label(text);
+              }
+            });
+          }
+        }
+      });
+    }
+
+    fn show_standings(
+      ui: &mut egui::Ui,
+      data: &AppData,
+      sort: &mut standings::Column,
+      descending: &mut bool,
+    ) {
+      use standings::Column;
+
+      let abbrev = |team_api_id: i32| {
+        find_team(&data.teams, team_api_id)
+          .map(|team| team.abbrev.clone())
+          .unwrap_or_default()
+      };
+      let mut rows: Vec<(usize, &standings::Standing)> =
+        data.standings.iter().enumerate().collect();
+      rows.sort_by(|(a_rank, a), (b_rank, b)| {
+        let ordering = match *sort {
+          Column::Rank => a_rank.cmp(b_rank),
+          Column::Team => abbrev(a.team_api_id).cmp(&abbrev(b.team_api_id)),
+          column => column.value(a).total_cmp(&column.value(b)),
+        };
+        if *descending {
+          ordering.reverse()
+        } else {
+          ordering
+        }
+      });
+
+      egui::ScrollArea::vertical().show(ui, |sui| {
+        egui::Grid::new("standings")
+          .striped(true)
+          .min_col_width(48.0)
+          .show(sui, |gui| {
+            for column in Column::ALL {
+              let mut label = column.label().to_string();
+              if column == *sort {
+                label.push_str(if *descending { " v" } else { " ^" });
+              }
+              if gui.button(RichText::new(label).strong()).clicked() {
+                if column == *sort {
+                  *descending = !*descending;
+                } else {
+                  *sort = column;
+                  // Bigger is better for most columns.
+                  *descending = !matches!(column, Column::Rank | Column::Team);
+                }
+              }
+            }
+            gui.end_row();
+
+            for (rank, standing) in rows {
+              for column in Column::ALL {
+                match column {
+                  Column::Rank => gui.label(format!("{}", rank + 1)),
+                  Column::Team => match find_team(&data.teams, standing.team_api_id) {
+                    Some(team) => gui.label(RichText::new(&team.abbrev).color(team.color)),
+                    None => gui.label(""),
+                  },
+                  column => gui.label(column.text(standing)),
+                };
+              }
+              gui.end_row();
+            }
+          });
+      });
+    }
 
-      AppData {
-        games,
-        teams: teams.to_vec(),
+    while let Ok(api_response) = self.rx.try_recv() {
+      // Nothing gets sent without a db, since the buttons that send are disabled.
+      let Some(db) = &self.db else {
+        continue;
+      };
+      let result = api_response.and_then(|api_response| {
+        let season = api_response.season;
+        let summary = db.insert_schedules(api_response, &self.data.teams)?;
+        self
+          .status
+          .push(format!("saved season {season}: {summary}"));
+        self
+          .status
+          .extend(summary.changes.iter().map(|change| change.to_string()));
+        self.seasons = db.get_seasons()?;
+        // The response might only have some of the teams, so the db has the whole season.
+        match self.season {
+          Some(current) if current.api_id == season => {
+            self.data = db.app_data(&self.data.teams, &current)?;
+          }
+          Some(_) => (),
+          // The first season to be fetched into an empty db.
+          None => {
+            self.season = self.seasons.last().copied();
+            if let Some(current) = &self.season {
+              self.data = db.app_data(&self.data.teams, current)?;
+            }
+          }
+        }
+        Ok(())
+      });
+      if let Err(e) = result {
+        self.error = Some(e);
       }
     }
 
-    if let Ok(api_response) = self.rx.try_recv() {
-      let new_data = make_app_data(&api_response, &self.data.teams);
-      self.data = new_data.clone();
-      self
-        .db
-        .insert_schedules(api_response.schedules, &self.data.teams)
-        .unwrap();
+    if let Some(error) = &self.error {
+      let mut dismissed = false;
+      egui::TopBottomPanel::top("error").show(ctx, |ui| {
+        ui.horizontal(|hui| {
+          hui.label(RichText::new(error.to_string()).color(Color32::RED));
+          dismissed = hui.button("dismiss").clicked();
+        });
+      });
+      if dismissed {
+        self.error = None;
+      }
     }
 
     egui::TopBottomPanel::top("header/tabs").show(ctx, |ui| {
       ui.horizontal(|hui| {
-        if hui.button(_txt("regular season")).clicked() {
-          println!("reg")
-        }
-        if hui.button(_txt("playoffs")).clicked() {
-          println!("playoffs")
+        hui.selectable_value(
+          &mut self.view.tab,
+          Tab::RegularSeason,
+          _txt("regular season"),
+        );
+        hui.selectable_value(&mut self.view.tab, Tab::Playoffs, _txt("playoffs"));
+        hui.selectable_value(&mut self.view.tab, Tab::Standings, _txt("standings"));
+        if self.view.tab == Tab::Playoffs {
+          hui.separator();
+          hui.selectable_value(
+            &mut self.view.playoffs_view,
+            PlayoffsView::Wins,
+            _txt("series"),
+          );
+          hui.selectable_value(
+            &mut self.view.playoffs_view,
+            PlayoffsView::Bracket,
+            _txt("bracket"),
+          );
         }
       });
     });
 
     egui::SidePanel::left("options").show(ctx, |ui| {
+      let prev_season = self.season;
+      egui::ComboBox::from_label("Season")
+        .selected_text(self.season.map(|season| season.label()).unwrap_or_default())
+        .show_ui(ui, |cui| {
+          for season in &self.seasons {
+            cui.selectable_value(&mut self.season, Some(*season), season.label());
+          }
+        });
+      ui.horizontal(|hui| {
+        hui.add(
+          egui::TextEdit::singleline(&mut self.new_season)
+            .hint_text("20242025")
+            .desired_width(80.0),
+        );
+        let api_id = parse_season(&self.new_season);
+        let add = hui.add_enabled(
+          api_id.is_some() && self.db.is_some(),
+          egui::Button::new("add season"),
+        );
+        if let (true, Some(api_id), Some(db)) = (add.clicked(), api_id, &self.db) {
+          // It's empty until "update" fetches it.
+          let season = db.season_id(api_id).and_then(|db_id| {
+            self.seasons = db.get_seasons()?;
+            Ok(Season { db_id, api_id })
+          });
+          match season {
+            Ok(season) => {
+              self.season = Some(season);
+              self.new_season.clear();
+            }
+            Err(e) => self.error = Some(e),
+          }
+        }
+      });
+      if let (true, Some(db), Some(season)) = (self.season != prev_season, &self.db, self.season) {
+        match db.app_data(&self.data.teams, &season) {
+          Ok(data) => self.data = data,
+          Err(e) => {
+            self.data = AppData::empty(self.data.teams.clone());
+            self.error = Some(e);
+          }
+        }
+      }
+
+      egui::ComboBox::from_label("Points")
+        .selected_text(self.view.points_system.label())
+        .show_ui(ui, |cui| {
+          for points_system in PointsSystem::ALL {
+            cui.selectable_value(
+              &mut self.view.points_system,
+              points_system,
+              points_system.label(),
+            );
+          }
+        });
+
+      egui::ComboBox::from_label("X axis")
+        .selected_text(self.view.x_axis.label())
+        .show_ui(ui, |cui| {
+          for x_axis in XAxis::ALL {
+            cui.selectable_value(&mut self.view.x_axis, x_axis, x_axis.label());
+          }
+        });
+
+      let max_points = self.view.points_system.max_points();
+      egui::ComboBox::from_label("Pace")
+        .selected_text(self.view.pace.label())
+        .show_ui(ui, |cui| {
+          let fraction = match self.view.pace {
+            Pace::Fraction(fraction) => fraction,
+            _ => 0.5,
+          };
+          for pace in [
+            Pace::Fraction(fraction),
+            Pace::PlayoffCutLine,
+            Pace::LeagueAverage,
+            Pace::Zero,
+          ] {
+            cui.selectable_value(&mut self.view.pace, pace, pace.label());
+          }
+        });
+      if let Pace::Fraction(fraction) = &mut self.view.pace {
+        // The slider shows points per game, but the fraction is what's stored so the pace stays
+        // the same when switching points systems.
+        ui.add(
+          egui::Slider::new(fraction, 0.0..=1.0)
+            .custom_formatter(move |fraction, _| format!("{:.2}", fraction * max_points as f64))
+            .custom_parser(move |text| {
+              text
+                .parse::<f64>()
+                .ok()
+                .map(|points| points / max_points as f64)
+            }),
+        );
+      }
+
+      ui.checkbox(&mut self.view.playoff_picture, "playoff picture");
+
       ui.collapsing("Divisions", |cui| {
-        cui.checkbox(&mut self.show.metro, "Metro");
-        cui.checkbox(&mut self.show.pacific, "Pacific");
-        cui.checkbox(&mut self.show.atlantic, "Atlantic");
-        cui.checkbox(&mut self.show.central, "Central");
+        cui.checkbox(&mut self.view.show.metro, "Metro");
+        cui.checkbox(&mut self.view.show.pacific, "Pacific");
+        cui.checkbox(&mut self.view.show.atlantic, "Atlantic");
+        cui.checkbox(&mut self.view.show.central, "Central");
+      });
+
+      ui.collapsing("Teams", |cui| {
+        fn shortcuts(
+          ui: &mut egui::Ui,
+          label: &str,
+          show: &mut ShowDivision,
+          teams: &[Team],
+          group: impl Fn(&Team) -> bool + Copy,
+        ) {
+          ui.label(label);
+          if ui.small_button("all").clicked() {
+            show.set_teams(teams, group, true);
+          }
+          if ui.small_button("none").clicked() {
+            show.set_teams(teams, group, false);
+          }
+          ui.end_row();
+        }
+
+        egui::Grid::new("team shortcuts").show(cui, |gui| {
+          for conference in Conference::ALL {
+            shortcuts(
+              gui,
+              conference.label(),
+              &mut self.view.show,
+              &self.data.teams,
+              |team| team.conference == conference,
+            );
+          }
+          for division in Division::ALL {
+            shortcuts(
+              gui,
+              division.label(),
+              &mut self.view.show,
+              &self.data.teams,
+              |team| team.division == division,
+            );
+          }
+        });
+
+        cui.add(egui::TextEdit::singleline(&mut self.team_search).hint_text("search"));
+        let search = self.team_search.trim().to_uppercase();
+        let mut teams: Vec<&Team> = self
+          .data
+          .teams
+          .iter()
+          .filter(|team| team.abbrev.contains(&search))
+          .collect();
+        teams.sort_by(|a, b| a.abbrev.cmp(&b.abbrev));
+        egui::ScrollArea::vertical()
+          .max_height(300.0)
+          .show(cui, |sui| {
+            for team in teams {
+              let mut shown = self.view.show.team(team);
+              let text = RichText::new(&team.abbrev).color(team.color);
+              if sui.checkbox(&mut shown, text).changed() {
+                self.view.show.set_teams(
+                  &self.data.teams,
+                  |other| other.api_id == team.api_id,
+                  shown,
+                );
+              }
+            }
+          });
       });
 
-      if ui.button("update").clicked() {
-        let tx: std::sync::mpsc::Sender<json::ApiResponse> = self.tx.clone();
+      if ui.button("reset view").clicked() {
+        self.view = View::default();
+        self.reset_plot = true;
+      }
+
+      let update = ui
+        .add_enabled(
+          self.db.is_some() && self.season.is_some(),
+          egui::Button::new("update"),
+        )
+        .on_hover_text(format!("fetch this season from {}", self.source.describe()));
+      if let (true, Some(db), Some(season)) = (update.clicked(), &self.db, self.season) {
+        let tx: std::sync::mpsc::Sender<Result<json::ApiResponse, Error>> = self.tx.clone();
         let ctx_ = ctx.clone();
 
-        let teams = self.db.get_teams();
-        tokio::spawn(async move {
-          match json::api::load_games(teams).await {
-            Ok(api_response) => {
-              let _ = tx.send(api_response);
+        // Only the teams that still have games to play.
+        let teams: Result<Vec<Team>, Error> = db
+          .unfinished_teams(&self.data.teams, season.api_id)
+          .map(|team_api_ids| {
+            let mut teams = self.data.teams.clone();
+            teams.retain(|team| team_api_ids.contains(&team.api_id));
+            teams
+          });
+        self.status.clear();
+        match teams {
+          Ok(teams) if teams.is_empty() => {
+            self
+              .status
+              .push(format!("season {} is over, nothing to sync", season.api_id));
+          }
+          Ok(teams) => {
+            let source = self.source.clone();
+            tokio::spawn(async move {
+              let _ = tx.send(source.load_season(&teams, season.api_id).await);
               ctx_.request_repaint();
+            });
+          }
+          Err(e) => self.error = Some(e),
+        }
+      }
+
+      ui.separator();
+      ui.label("Schedule directory");
+      ui.text_edit_singleline(&mut self.import_dir);
+      let import = ui.add_enabled(
+        self.db.is_some(),
+        egui::Button::new("import from directory"),
+      );
+      if import.clicked() {
+        self.status.clear();
+        match json::files::load_dir(Path::new(&self.import_dir)) {
+          Ok(loaded) => {
+            for (path, reason) in &loaded.skipped {
+              self
+                .status
+                .push(format!("skipped {}: {reason}", path.display()));
             }
-            Err(e) => println!("error from NHL api: {:?}", e),
+            for api_response in loaded.api_responses {
+              let _ = self.tx.send(Ok(api_response));
+            }
+            ctx.request_repaint();
+          }
+          Err(e) => self.error = Some(e),
+        }
+      }
+      egui::ScrollArea::vertical()
+        .id_source("status")
+        .max_height(150.0)
+        .show(ui, |sui| {
+          for line in &self.status {
+            sui.label(line);
           }
         });
+
+      ui.separator();
+      ui.label("Export visible series");
+      ui.text_edit_singleline(&mut self.export_path);
+      ui.horizontal(|hui| {
+        for format in [ExportFormat::Csv, ExportFormat::Json] {
+          if hui
+            .button(format!("export {}", format.extension()))
+            .clicked()
+          {
+            match self.export_series(format) {
+              Ok(path) => self.exported = Some(path),
+              Err(e) => self.error = Some(e),
+            }
+          }
+        }
+      });
+      if let Some(path) = &self.exported {
+        ui.label(format!("wrote {}", path.display()));
       }
     });
 
-    egui::CentralPanel::default().show(ctx, |ui| {
-      egui_plot::Plot::new("plot")
-        .legend(Legend::default().text_style(egui::TextStyle::Heading))
-        .show(ui, |plot_ui| {
-          fn show_team(show: ShowDivision, team: &Team) -> bool {
-            match team.division {
-              Division::Metropolitan => show.metro,
-              Division::Central => show.central,
-              Division::Pacific => show.pacific,
-              Division::Atlantic => show.atlantic,
+    let paces: HashMap<Conference, f32> = Conference::ALL
+      .into_iter()
+      .map(|conference| {
+        let pace = self
+          .view
+          .pace
+          .points_per_game(self.view.points_system, &self.data, conference);
+        (conference, pace)
+      })
+      .collect();
+    let origin = self.view.x_axis.origin(&self.data.games);
+    let seeds = standings::seeds(&self.data.standings, &self.data.teams);
+    let team_lines = self.team_lines(origin, &seeds);
+
+    if self.view.tab == Tab::RegularSeason {
+      egui::SidePanel::right("legend").show(ctx, |ui| {
+        ui.checkbox(&mut self.view.show.only_selected, "show only selected");
+        let clear = egui::Button::new("clear selection");
+        if ui
+          .add_enabled(!self.view.show.selected.is_empty(), clear)
+          .clicked()
+        {
+          self.view.show.selected.clear();
+        }
+        ui.label("ctrl-click to select several teams");
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |sui| {
+          for line in &team_lines {
+            let selected = self.view.show.selected.contains(&line.team_api_id);
+            let text = RichText::new(&line.name).color(line.color).heading();
+            if sui.selectable_label(selected, text).clicked() {
+              let modifiers = sui.input(|i| i.modifiers);
+              self.view.show.click(line.team_api_id, modifiers);
             }
           }
-          for (team_id, games_) in &self.data.games {
-            let team: &Team = self
-              .data
-              .teams
-              .iter()
-              .find(|team| team.api_id == *team_id)
-              .unwrap();
-            if show_team(self.show, team) {
-              let mut games: Vec<PlotPoint> = vec![];
-              let mut points_so_far = 0.0;
-
-              games.push(PlotPoint { x: 0.0, y: 0.0 });
-
-              for (idx, game) in games_.iter().enumerate() {
-                if let Some(points_) = game.points {
-                  points_so_far += points_ - 1.0;
-                  games.push(PlotPoint {
-                    x: (1 + idx) as f64,
-                    y: points_so_far as f64,
-                  })
+        });
+      });
+    }
+
+    egui::CentralPanel::default().show(ctx, |ui| {
+      match self.view.tab {
+        Tab::Standings => show_standings(
+          ui,
+          &self.data,
+          &mut self.view.standings_sort,
+          &mut self.view.standings_descending,
+        ),
+        Tab::Playoffs => match self.view.playoffs_view {
+          PlayoffsView::Wins => show_series_wins(ui, &self.data),
+          PlayoffsView::Bracket => show_bracket(ui, &self.data),
+        },
+        Tab::RegularSeason => {
+          let y_axis_label = self.view.pace.y_axis_label(self.view.points_system);
+          // Key is the line's name.
+          let hover_text: HashMap<String, Vec<(f64, String)>> = team_lines
+            .iter()
+            .map(|line| (line.name.clone(), line.hover_text.clone()))
+            .collect();
+
+          let x_axis = self.view.x_axis;
+          // The legend panel replaces egui_plot's legend, which hides lines when clicked.
+          let mut plot = egui_plot::Plot::new("plot")
+            .x_axis_label(self.view.x_axis.label())
+            .y_axis_label(y_axis_label.clone())
+            .label_formatter(move |name, value| {
+              let game = hover_text.get(name).and_then(|games| {
+                games
+                  .iter()
+                  .find(|(x, _)| (x - value.x).abs() < 0.5)
+                  .map(|(_, description)| description)
+              });
+              let position = match x_axis {
+                XAxis::Date => XAxis::date(value.x)
+                  .map(|date| date.format("%b %-d, %Y").to_string())
+                  .unwrap_or_default(),
+                XAxis::GameNumber => format!("game {:.0}", value.x),
+              };
+              match game {
+                Some(description) => {
+                  format!("{name}\n{description}\n{:.2} {y_axis_label}", value.y)
                 }
+                // Like the pace and cut lines.
+                None if !name.is_empty() => format!("{name}\n{position}\n{:.2}", value.y),
+                None => format!("{position}\n{:.2}", value.y),
               }
-
-              let game_points = PlotPoints::Owned(games.clone());
+            });
+          if std::mem::take(&mut self.reset_plot) {
+            plot = plot.reset();
+          }
+          if self.view.x_axis == XAxis::Date {
+            plot = plot
+              .x_grid_spacer(date_grid)
+              .x_axis_formatter(|mark, _, _| {
+                XAxis::date(mark.value)
+                  .map(|date| date.format("%b %-d").to_string())
+                  .unwrap_or_default()
+              });
+          }
+          let response = plot.show(ui, |plot_ui| {
+            if self.view.pace != Pace::Zero {
+              let per_game = match self.view.pace {
+                Pace::PlayoffCutLine => Conference::ALL
+                  .iter()
+                  .map(|conference| format!("{} {:.2}", conference.label(), paces[conference]))
+                  .collect::<Vec<_>>()
+                  .join(", "),
+                _ => format!("{:.2}", paces[&Conference::Eastern]),
+              };
+              plot_ui.hline(
+                egui_plot::HLine::new(0.0)
+                  .name(format!("pace: {} ({per_game}/gp)", self.view.pace.label()))
+                  .color(Color32::GRAY),
+              );
+            }
+            if self.view.playoff_picture {
+              // The conference's cut line is the pace of its last wild card team, drawn along that
+              // team's schedule. It's the same team that `Pace::PlayoffCutLine` uses.
+              for conference in Conference::ALL {
+                let Some(cut_line_games) =
+                  standings::cut_line_team(&seeds, &self.data.teams, conference)
+                    .and_then(|team_api_id| self.data.games.get(&team_api_id))
+                else {
+                  continue;
+                };
+                let pace = paces[&conference];
+                let Some(cut) = self.view.points_system.points_per_game(cut_line_games) else {
+                  continue;
+                };
+                let y = |games_played: usize| match self.view.points_system {
+                  PointsSystem::PointsPercentage => {
+                    ((cut - pace) / self.view.points_system.max_points()) as f64
+                  }
+                  _ => ((cut - pace) * games_played as f32) as f64,
+                };
+                let mut cut_line = vec![[origin, y(0)]];
+                for (idx, game) in cut_line_games.iter().enumerate() {
+                  cut_line.push([self.view.x_axis.x(idx, game), y(1 + idx)]);
+                }
+                let name = format!("{} cut line ({cut:.2}/gp)", conference.label());
+                plot_ui.line(
+                  Line::new(cut_line)
+                    .name(name)
+                    .color(Color32::LIGHT_GRAY)
+                    .style(egui_plot::LineStyle::dashed_loose()),
+                );
+              }
+            }
+            // Selected lines are drawn last so they end up on top.
+            let (selected, others): (Vec<&TeamLine>, Vec<&TeamLine>) = team_lines
+              .iter()
+              .partition(|line| self.view.show.selected.contains(&line.team_api_id));
+            for line in others.into_iter().chain(selected) {
+              let dimmed = self.view.show.is_dimmed(line.team_api_id);
+              if dimmed && self.view.show.only_selected {
+                continue;
+              }
+              let (color, width) = match (dimmed, self.view.show.selected.is_empty()) {
+                (true, _) => (line.color.gamma_multiply(0.2), 1.0),
+                (false, true) => (line.color, 1.5),
+                (false, false) => (line.color, 3.0),
+              };
               plot_ui.line(
-                Line::new(game_points)
-                  .name(team.abbrev.clone())
-                  .color(team.color),
+                Line::new(PlotPoints::Owned(line.points.clone()))
+                  .id(line.id())
+                  .name(&line.name)
+                  .color(color)
+                  .width(width),
               );
             }
+          });
+          if response.response.clicked() {
+            let clicked_line = team_lines
+              .iter()
+              .find(|line| response.hovered_plot_item == Some(line.id()));
+            if let Some(line) = clicked_line {
+              let modifiers = ui.input(|i| i.modifiers);
+              self.view.show.click(line.team_api_id, modifiers);
+            }
           }
-        });
+        }
+      }
 
       if ui.input(|i| i.modifiers.shift_only() && i.key_pressed(egui::Key::Q)) {
         ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close)